@@ -9,6 +9,14 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("inser_entity", |b| {
         b.iter(|| parse_wql("insert {a: 1, b: 2.3, c: 'g', d: \"str\",} into my_entity"))
     });
+
+    c.bench_function("insert_many_entity", |b| {
+        b.iter(|| {
+            parse_wql(
+                "insert [{a: 1, b: 2.3, c: 'g', d: \"str\",}, {a: 2, b: 4.6, c: 'h', d: \"str\",}] INTO my_entity",
+            )
+        })
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
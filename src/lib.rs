@@ -2,6 +2,12 @@ pub(crate) mod logic;
 pub(crate) mod model;
 pub(crate) mod parser; // pub(crate) mod parser;
 
-pub use model::types::Types;
-pub use model::Wql;
-pub use parser::parse_wql;
+pub use model::clauses::{Clause, Comparison};
+pub use model::error::WqlError;
+pub use model::explain::Explanation;
+pub use model::types::{ContentOp, PathSegment, Placeholder, SchemaType, Types};
+pub use model::{
+    Alteration, ConflictAction, CreateOptions, Order, PreparedWql, RelationMode, RelationType,
+    SelectFunction, ToSelect, Wql,
+};
+pub use parser::{parse_wql, parse_wql_script, prepare_wql};
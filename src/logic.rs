@@ -1,3 +1,111 @@
 pub fn integer_decode(val: f64) -> u64 {
     val.to_bits()
 }
+
+// Splits on `separator` when it is not inside a string, a char or any brackets
+pub fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c, depth) in top_level_chars(input) {
+        if c == separator && depth == 0 {
+            parts.push(&input[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+// Position of the bracket that closes the one `input` starts with
+pub fn closing_bracket(input: &str) -> Option<usize> {
+    top_level_chars(input)
+        .skip(1)
+        .find(|(_, c, depth)| *depth == 1 && [')', ']', '}'].contains(c))
+        .map(|(i, _, _)| i)
+}
+
+// Chars outside of strings, chars and comments, with their bracket depth
+fn top_level_chars(input: &str) -> impl Iterator<Item = (usize, char, usize)> + '_ {
+    let mut depth = 0_usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut skip_until = 0_usize;
+    input.char_indices().filter_map(move |(i, c)| {
+        if i < skip_until {
+            return None;
+        }
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            return None;
+        }
+        // an unterminated block comment is kept, so that parsing it fails
+        let rest = &input[i..];
+        if rest.starts_with("--") {
+            skip_until = rest.find('\n').map_or(input.len(), |end| i + end + 1);
+            return None;
+        }
+        if let Some(end) = rest.strip_prefix("/*").and_then(|r| r.find("*/")) {
+            skip_until = i + 2 + end + 2;
+            return None;
+        }
+        let current = depth;
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                return None;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        Some((i, c, current))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_top_level_test() {
+        assert_eq!(
+            split_top_level(
+                "SELECT #{a, b} FROM x WHERE {a in [1i, 2i], b == \"c, d\"}, SELECT * FROM y",
+                ','
+            ),
+            vec![
+                "SELECT #{a, b} FROM x WHERE {a in [1i, 2i], b == \"c, d\"}",
+                " SELECT * FROM y"
+            ]
+        );
+        assert_eq!(
+            split_top_level("INSERT {a: ',', b: \"\\\";\"} INTO x; COMMIT", ';'),
+            vec!["INSERT {a: ',', b: \"\\\";\"} INTO x", " COMMIT"]
+        );
+    }
+
+    #[test]
+    fn closing_bracket_test() {
+        assert_eq!(closing_bracket("(a, (b), \")\") rest"), Some(12));
+        assert_eq!(closing_bracket("(a, (b)"), None);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(
+            split_top_level("SHOW ENTITIES; -- a; b\n/* c; ( */ COMMIT; \"--;\"", ';'),
+            vec!["SHOW ENTITIES", " -- a; b\n/* c; ( */ COMMIT", " \"--;\""]
+        );
+        assert_eq!(closing_bracket("(a /* ) */, b) rest"), Some(13));
+        assert_eq!(
+            split_top_level("SHOW ENTITIES; /* unterminated ; SHOW ENTITIES", ';'),
+            vec!["SHOW ENTITIES", " /* unterminated ", " SHOW ENTITIES"]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::Types;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Clause {
+    Compare(String, Comparison, Types),
+    In(String, Vec<Types>),
+    Between(String, Types, Types),
+    Like(String, String),
+    And(Vec<Clause>),
+    Or(Vec<Clause>),
+    Not(Box<Clause>),
+}
+
+impl Clause {
+    pub(crate) fn try_map<F>(self, f: &mut F) -> Result<Clause, String>
+    where
+        F: FnMut(Types) -> Result<Types, String>,
+    {
+        Ok(match self {
+            Clause::Compare(key, comparison, value) => {
+                Clause::Compare(key, comparison, value.try_map(f)?)
+            }
+            Clause::In(key, values) => Clause::In(
+                key,
+                values
+                    .into_iter()
+                    .map(|t| t.try_map(f))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Clause::Between(key, start, end) => {
+                Clause::Between(key, start.try_map(f)?, end.try_map(f)?)
+            }
+            Clause::Like(key, pattern) => Clause::Like(key, pattern),
+            Clause::And(clauses) => Clause::And(try_map_clauses(clauses, f)?),
+            Clause::Or(clauses) => Clause::Or(try_map_clauses(clauses, f)?),
+            Clause::Not(clause) => Clause::Not(Box::new(clause.try_map(f)?)),
+        })
+    }
+}
+
+pub(crate) fn try_map_clauses<F>(clauses: Vec<Clause>, f: &mut F) -> Result<Vec<Clause>, String>
+where
+    F: FnMut(Types) -> Result<Types, String>,
+{
+    clauses.into_iter().map(|c| c.try_map(f)).collect()
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Lower,
+    LowerOrEqual,
+}
+
+impl From<&str> for Comparison {
+    fn from(i: &str) -> Self {
+        match i {
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "<" => Comparison::Lower,
+            "<=" => Comparison::LowerOrEqual,
+            _ => unimplemented!("no other comparison supported"),
+        }
+    }
+}
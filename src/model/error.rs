@@ -5,6 +5,7 @@ pub enum WqlError<'b> {
     Plain(String),
     UuidParse(uuid::Error),
     Parse(nom::Err<VerboseError<&'b str>>),
+    Script(usize, Box<WqlError<'b>>),
 }
 
 impl<'b> From<nom::Err<VerboseError<&'b str>>> for WqlError<'b> {
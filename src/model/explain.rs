@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{clauses::Clause, Alteration, CreateOptions, ToSelect, Wql};
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Explanation {
+    pub entities: BTreeSet<String>,
+    pub ids: BTreeSet<Uuid>,
+    pub keys: BTreeSet<String>,
+    pub read_only: bool,
+    pub encrypted: BTreeSet<String>,
+}
+
+impl Wql {
+    // What the statement touches. `creates` are the `CreateEntity` of the entities involved,
+    // used to find their `ENCRYPT` keys
+    pub fn explain(&self, creates: &[Wql]) -> Explanation {
+        let mut explanation = Explanation {
+            read_only: true,
+            ..Explanation::default()
+        };
+        explanation.add(self);
+
+        // `ENCRYPT` keys by entity, following creations and alterations in order
+        let mut encrypted: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        for declaration in creates.iter().chain(self.statements()) {
+            match declaration {
+                Wql::CreateEntity { name, encrypts, .. } => {
+                    encrypted.insert(name, encrypts.iter().flatten().cloned().collect());
+                }
+                Wql::AlterEntity { name, changes } => {
+                    let keys = encrypted.entry(name).or_default();
+                    for change in changes {
+                        match change {
+                            Alteration::Add(CreateOptions::ENCRYPT, added) => {
+                                keys.extend(added.iter().cloned())
+                            }
+                            Alteration::Drop(CreateOptions::ENCRYPT, dropped) => {
+                                dropped.iter().for_each(|key| {
+                                    keys.remove(key);
+                                })
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        explanation.encrypted = encrypted
+            .into_iter()
+            .filter(|(name, _)| explanation.entities.contains(*name))
+            .flat_map(|(_, keys)| keys)
+            .collect();
+        explanation
+    }
+
+    fn statements(&self) -> Vec<&Wql> {
+        match self {
+            Wql::Transaction(queries) => queries.iter().flat_map(Wql::statements).collect(),
+            Wql::Explain(query) => query.statements(),
+            wql => vec![wql],
+        }
+    }
+}
+
+impl Explanation {
+    fn add(&mut self, wql: &Wql) {
+        match wql {
+            Wql::CreateEntity {
+                name,
+                uniques,
+                encrypts,
+                schema,
+                ..
+            } => {
+                self.write(name);
+                self.keys.extend(uniques.iter().flatten().cloned());
+                self.keys.extend(encrypts.iter().flatten().cloned());
+                self.keys
+                    .extend(schema.iter().flat_map(|s| s.keys()).cloned());
+            }
+            Wql::AlterEntity { name, changes } => {
+                self.write(name);
+                self.keys.extend(
+                    changes
+                        .iter()
+                        .flat_map(|change| match change {
+                            Alteration::Add(_, keys) | Alteration::Drop(_, keys) => keys,
+                        })
+                        .cloned(),
+                );
+            }
+            Wql::ShowEntities => (),
+            Wql::Describe { entity } => {
+                self.entities.insert(entity.to_owned());
+            }
+            Wql::DropEntity { name } => self.write(name),
+            Wql::Insert {
+                entity,
+                content,
+                id,
+                ..
+            }
+            | Wql::Upsert {
+                entity,
+                content,
+                id,
+                ..
+            } => {
+                self.write(entity);
+                self.ids.extend(id);
+                self.keys.extend(content.keys().cloned());
+            }
+            Wql::InsertMany { entity, contents } => {
+                self.write(entity);
+                self.keys
+                    .extend(contents.iter().flat_map(|c| c.keys()).cloned());
+            }
+            Wql::UpdateSet { name, id, content } => {
+                self.write(name);
+                self.ids.insert(*id);
+                self.keys.extend(content.keys().cloned());
+            }
+            Wql::UpdateContent { name, id, content } => {
+                self.write(name);
+                self.ids.insert(*id);
+                self.keys.extend(content.keys().cloned());
+            }
+            Wql::Evict { entity, id, keys } => {
+                self.write(entity);
+                self.ids.extend(id);
+                self.keys.extend(keys.iter().flatten().cloned());
+            }
+            Wql::Delete { entity, id, .. } => {
+                self.write(entity);
+                self.ids.insert(*id);
+            }
+            Wql::Select {
+                entity, arg, id, ..
+            } => {
+                self.read(entity, arg);
+                self.ids.extend(id);
+            }
+            Wql::SelectIds {
+                entity, arg, ids, ..
+            } => {
+                self.read(entity, arg);
+                self.ids.extend(ids);
+            }
+            Wql::SelectWhere {
+                entity,
+                arg,
+                clauses,
+                ..
+            } => {
+                self.read(entity, arg);
+                clauses.iter().for_each(|clause| self.clause(clause));
+            }
+            Wql::SelectWhen {
+                entity, arg, id, ..
+            }
+            | Wql::SelectWhenRange {
+                entity, arg, id, ..
+            } => {
+                self.read(entity, arg);
+                self.ids.extend(id);
+            }
+            Wql::Check {
+                entity,
+                id,
+                content,
+            } => {
+                self.entities.insert(entity.to_owned());
+                self.ids.insert(*id);
+                self.keys.extend(content.keys().cloned());
+            }
+            Wql::MatchUpdate {
+                entity,
+                id,
+                content,
+                conditions,
+            } => {
+                self.write(entity);
+                self.ids.insert(*id);
+                self.keys.extend(content.keys().cloned());
+                self.clause(conditions);
+            }
+            Wql::Relation {
+                queries: (first, second),
+                ..
+            }
+            | Wql::Join {
+                queries: (first, second),
+                ..
+            } => {
+                self.add(first);
+                self.add(second);
+            }
+            Wql::Transaction(queries) => queries.iter().for_each(|query| self.add(query)),
+            Wql::Explain(query) => self.add(query),
+        }
+    }
+
+    fn write(&mut self, entity: &str) {
+        self.entities.insert(entity.to_owned());
+        self.read_only = false;
+    }
+
+    fn read(&mut self, entity: &str, arg: &ToSelect) {
+        self.entities.insert(entity.to_owned());
+        if let ToSelect::Keys(keys) = arg {
+            self.keys.extend(keys.iter().cloned());
+        }
+    }
+
+    fn clause(&mut self, clause: &Clause) {
+        match clause {
+            Clause::Compare(key, ..)
+            | Clause::In(key, _)
+            | Clause::Between(key, ..)
+            | Clause::Like(key, _) => {
+                self.keys.insert(key.to_owned());
+            }
+            Clause::And(clauses) | Clause::Or(clauses) => {
+                clauses.iter().for_each(|clause| self.clause(clause))
+            }
+            Clause::Not(clause) => self.clause(clause),
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: ToString>(items: impl IntoIterator<Item = T>) -> String {
+            let items = items
+                .into_iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>();
+            if items.is_empty() {
+                String::from("none")
+            } else {
+                items.join(", ")
+            }
+        }
+
+        writeln!(f, "entities: {}", list(&self.entities))?;
+        writeln!(f, "ids: {}", list(&self.ids))?;
+        writeln!(f, "keys: {}", list(&self.keys))?;
+        writeln!(f, "read only: {}", self.read_only)?;
+        write!(f, "encrypted: {}", list(&self.encrypted))
+    }
+}
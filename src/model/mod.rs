@@ -1,11 +1,17 @@
+pub mod clauses;
 pub mod error;
+pub mod explain;
 pub mod types;
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use self::types::Types;
+use self::{
+    clauses::{try_map_clauses, Clause},
+    types::{try_map_content, ContentOp, Placeholder, SchemaType, Types},
+};
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Wql {
@@ -13,11 +19,36 @@ pub enum Wql {
         name: String,
         uniques: Option<Vec<String>>,
         encrypts: Option<Vec<String>>,
+        schema: Option<HashMap<String, SchemaType>>,
+        if_not_exists: bool,
+    },
+    AlterEntity {
+        name: String,
+        changes: Vec<Alteration>,
+    },
+    ShowEntities,
+    Describe {
+        entity: String,
+    },
+    DropEntity {
+        name: String,
     },
     Insert {
         entity: String,
         content: HashMap<String, Types>,
         id: Option<Uuid>,
+        if_not_exists: bool,
+    },
+    InsertMany {
+        entity: String,
+        contents: Vec<HashMap<String, Types>>,
+    },
+    Upsert {
+        entity: String,
+        content: HashMap<String, Types>,
+        id: Option<Uuid>,
+        conflict: Vec<String>,
+        action: ConflictAction,
     },
     UpdateSet {
         name: String,
@@ -27,19 +58,324 @@ pub enum Wql {
     UpdateContent {
         name: String,
         id: Uuid,
-        content: HashMap<String, Types>,
+        content: HashMap<String, ContentOp>,
     },
     Evict {
         entity: String,
         id: Option<Uuid>,
+        keys: Option<Vec<String>>,
     },
     Delete {
         entity: String,
         id: Uuid,
-        // at: DateTime<Utc>
+        at: Option<DateTime<Utc>>,
     },
+    Select {
+        entity: String,
+        arg: ToSelect,
+        id: Option<Uuid>,
+        functions: Vec<SelectFunction>,
+    },
+    SelectIds {
+        entity: String,
+        arg: ToSelect,
+        ids: Vec<Uuid>,
+        functions: Vec<SelectFunction>,
+    },
+    SelectWhere {
+        entity: String,
+        arg: ToSelect,
+        clauses: Vec<Clause>,
+        functions: Vec<SelectFunction>,
+    },
+    SelectWhen {
+        entity: String,
+        arg: ToSelect,
+        id: Option<Uuid>,
+        time: DateTime<Utc>,
+    },
+    SelectWhenRange {
+        entity: String,
+        arg: ToSelect,
+        id: Option<Uuid>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    Check {
+        entity: String,
+        id: Uuid,
+        content: HashMap<String, Types>,
+    },
+    MatchUpdate {
+        entity: String,
+        id: Uuid,
+        content: HashMap<String, Types>,
+        conditions: Clause,
+    },
+    Relation {
+        operation: RelationType,
+        mode: RelationMode,
+        queries: (Box<Wql>, Box<Wql>),
+    },
+    Join {
+        entities: (String, String),
+        keys: (String, String),
+        queries: (Box<Wql>, Box<Wql>),
+    },
+    Transaction(Vec<Wql>),
+    Explain(Box<Wql>),
+}
+
+impl Wql {
+    // An `Upsert` conflict target must be declared as `UNIQUES` by its entity's `CreateEntity`
+    pub fn check_conflict_uniques(&self, create: &Wql) -> Result<(), String> {
+        match (self, create) {
+            (
+                Wql::Upsert {
+                    entity, conflict, ..
+                },
+                Wql::CreateEntity { name, uniques, .. },
+            ) if entity == name => {
+                let uniques = uniques.clone().unwrap_or_default();
+                match conflict.iter().find(|key| !uniques.contains(key)) {
+                    Some(key) => Err(format!(
+                        "Conflict key `{}` is not declared as UNIQUES for entity `{}`",
+                        key, entity
+                    )),
+                    None => Ok(()),
+                }
+            }
+            (Wql::Upsert { entity, .. }, Wql::CreateEntity { name, .. }) => Err(format!(
+                "Upsert on entity `{}` can't be checked against entity `{}`",
+                entity, name
+            )),
+            _ => Err(String::from(
+                "Only Upsert can be checked against a CreateEntity",
+            )),
+        }
+    }
+
+    // Values must match the `SCHEMA` of the entity's `CreateEntity`, `Nil` is always accepted
+    pub fn check_schema(&self, create: &Wql) -> Result<(), String> {
+        let (entity, contents) =
+            match self {
+                Wql::Insert {
+                    entity, content, ..
+                }
+                | Wql::Upsert {
+                    entity, content, ..
+                }
+                | Wql::UpdateSet {
+                    name: entity,
+                    content,
+                    ..
+                } => (entity, vec![content]),
+                Wql::InsertMany { entity, contents } => (entity, contents.iter().collect()),
+                _ => return Err(String::from(
+                    "Only Insert, InsertMany, Upsert and UpdateSet can be checked against a schema",
+                )),
+            };
+        match create {
+            Wql::CreateEntity { name, schema, .. } if name == entity => {
+                let schema = match schema {
+                    Some(schema) => schema,
+                    None => return Ok(()),
+                };
+                let mismatch =
+                    contents
+                        .iter()
+                        .flat_map(|content| content.iter())
+                        .find(|(key, value)| {
+                            schema.get(*key).is_some_and(|expected| {
+                                value.schema_type() != Some(*expected)
+                                    && !matches!(value, Types::Nil(_))
+                            })
+                        });
+                match mismatch {
+                    Some((key, value)) => Err(format!(
+                        "Key `{}` of entity `{}` expects {:?} but got {:?}",
+                        key, entity, schema[key], value
+                    )),
+                    None => Ok(()),
+                }
+            }
+            Wql::CreateEntity { name, .. } => Err(format!(
+                "Entity `{}` can't be checked against the schema of entity `{}`",
+                entity, name
+            )),
+            _ => Err(String::from("Schemas are declared by CreateEntity")),
+        }
+    }
+
+    // Applies `f` to every value of contents and clauses, including nested queries
+    pub(crate) fn try_map_values<F>(self, f: &mut F) -> Result<Wql, String>
+    where
+        F: FnMut(Types) -> Result<Types, String>,
+    {
+        Ok(match self {
+            Wql::Insert {
+                entity,
+                content,
+                id,
+                if_not_exists,
+            } => Wql::Insert {
+                entity,
+                content: try_map_content(content, f)?,
+                id,
+                if_not_exists,
+            },
+            Wql::InsertMany { entity, contents } => Wql::InsertMany {
+                entity,
+                contents: contents
+                    .into_iter()
+                    .map(|content| try_map_content(content, f))
+                    .collect::<Result<_, _>>()?,
+            },
+            Wql::Upsert {
+                entity,
+                content,
+                id,
+                conflict,
+                action,
+            } => Wql::Upsert {
+                entity,
+                content: try_map_content(content, f)?,
+                id,
+                conflict,
+                action,
+            },
+            Wql::UpdateSet { name, id, content } => Wql::UpdateSet {
+                name,
+                id,
+                content: try_map_content(content, f)?,
+            },
+            Wql::UpdateContent { name, id, content } => Wql::UpdateContent {
+                name,
+                id,
+                content: content
+                    .into_iter()
+                    .map(|(k, op)| op.try_map(f).map(|op| (k, op)))
+                    .collect::<Result<_, _>>()?,
+            },
+            Wql::Check {
+                entity,
+                id,
+                content,
+            } => Wql::Check {
+                entity,
+                id,
+                content: try_map_content(content, f)?,
+            },
+            Wql::MatchUpdate {
+                entity,
+                id,
+                content,
+                conditions,
+            } => Wql::MatchUpdate {
+                entity,
+                id,
+                content: try_map_content(content, f)?,
+                conditions: conditions.try_map(f)?,
+            },
+            Wql::SelectWhere {
+                entity,
+                arg,
+                clauses,
+                functions,
+            } => Wql::SelectWhere {
+                entity,
+                arg,
+                clauses: try_map_clauses(clauses, f)?,
+                functions,
+            },
+            Wql::Relation {
+                operation,
+                mode,
+                queries: (first, second),
+            } => Wql::Relation {
+                operation,
+                mode,
+                queries: (
+                    Box::new(first.try_map_values(f)?),
+                    Box::new(second.try_map_values(f)?),
+                ),
+            },
+            Wql::Join {
+                entities,
+                keys,
+                queries: (first, second),
+            } => Wql::Join {
+                entities,
+                keys,
+                queries: (
+                    Box::new(first.try_map_values(f)?),
+                    Box::new(second.try_map_values(f)?),
+                ),
+            },
+            Wql::Transaction(queries) => Wql::Transaction(
+                queries
+                    .into_iter()
+                    .map(|query| query.try_map_values(f))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Wql::Explain(query) => Wql::Explain(Box::new(query.try_map_values(f)?)),
+            wql => wql,
+        })
+    }
+}
+
+// A query with `$1` or `:name` placeholders, as returned by `prepare_wql`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PreparedWql {
+    query: Wql,
+}
+
+impl PreparedWql {
+    pub(crate) fn new(query: Wql) -> Self {
+        Self { query }
+    }
+
+    // Bound values are taken as they are, so they are never parsed as WQL,
+    // but they can't be placeholders themselves
+    pub fn bind(&self, params: &HashMap<Placeholder, Types>) -> Result<Wql, String> {
+        self.query
+            .clone()
+            .try_map_values(&mut |value| match value {
+                Types::Placeholder(placeholder) => params
+                    .get(&placeholder)
+                    .cloned()
+                    .ok_or_else(|| format!("No value bound to placeholder {:?}", placeholder)),
+                value => Ok(value),
+            })?
+            .try_map_values(&mut |value| match value {
+                Types::Placeholder(placeholder) => Err(format!(
+                    "Placeholder {:?} can't be bound as a value",
+                    placeholder
+                )),
+                value => Ok(value),
+            })
+    }
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Alteration {
+    Add(CreateOptions, Vec<String>),
+    Drop(CreateOptions, Vec<String>),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum ConflictAction {
+    UpdateSet,
+    DoNothing,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum ToSelect {
+    All,
+    Keys(Vec<String>),
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq)]
 pub enum Operation {
     #[allow(non_camel_case_types)]
@@ -62,9 +398,24 @@ pub enum Operation {
     RELATION,
     #[allow(non_camel_case_types)]
     JOIN,
+    #[allow(non_camel_case_types)]
+    ALTER,
+    #[allow(non_camel_case_types)]
+    SHOW,
+    #[allow(non_camel_case_types)]
+    DESCRIBE,
+    #[allow(non_camel_case_types)]
+    DROP,
+    #[allow(non_camel_case_types)]
+    BEGIN,
+    #[allow(non_camel_case_types)]
+    COMMIT,
+    #[allow(non_camel_case_types)]
+    EXPLAIN,
 }
 
-#[derive(Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum CreateOptions {
     #[allow(non_camel_case_types)]
     UNIQUES,
@@ -72,6 +423,65 @@ pub enum CreateOptions {
     ENCRYPT,
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum RelationType {
+    Intersect,
+    Difference,
+    Union,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum RelationMode {
+    Key,
+    Hash,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum SelectFunction {
+    OrderBy(String, Order),
+    Limit(usize),
+    Offset(usize),
+    Dedup(String),
+    Count,
+    GroupBy(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl Order {
+    // Values that `Types` can't compare are kept in place
+    pub fn compare(&self, a: &Types, b: &Types) -> Ordering {
+        let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+        match self {
+            Order::Asc => ordering,
+            Order::Desc => ordering.reverse(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SelectLookup {
+    Id(Uuid),
+    Ids(Vec<Uuid>),
+    Where(Vec<Clause>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpdatePayload {
+    Set(HashMap<String, Types>),
+    Content(HashMap<String, ContentOp>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum When {
+    At(DateTime<Utc>),
+    Range(DateTime<Utc>, DateTime<Utc>),
+}
+
 impl From<&str> for Operation {
     fn from(i: &str) -> Self {
         match i.to_uppercase().as_str() {
@@ -85,6 +495,13 @@ impl From<&str> for Operation {
             "CHECK" => Operation::CHECK,
             "RELATION" => Operation::RELATION,
             "JOIN" => Operation::JOIN,
+            "ALTER" => Operation::ALTER,
+            "SHOW" => Operation::SHOW,
+            "DESCRIBE" => Operation::DESCRIBE,
+            "DROP" => Operation::DROP,
+            "BEGIN" => Operation::BEGIN,
+            "COMMIT" => Operation::COMMIT,
+            "EXPLAIN" => Operation::EXPLAIN,
             _ => unimplemented!("no other operation supported"),
         }
     }
@@ -100,6 +517,37 @@ impl From<&str> for CreateOptions {
     }
 }
 
+impl From<&str> for RelationType {
+    fn from(i: &str) -> Self {
+        match i.to_uppercase().as_str() {
+            "INTERSECT" => RelationType::Intersect,
+            "DIFFERENCE" => RelationType::Difference,
+            "UNION" => RelationType::Union,
+            _ => unimplemented!("no other relation supported"),
+        }
+    }
+}
+
+impl From<&str> for RelationMode {
+    fn from(i: &str) -> Self {
+        match i.to_uppercase().as_str() {
+            "KEY" => RelationMode::Key,
+            "HASH" => RelationMode::Hash,
+            _ => unimplemented!("no other relation mode supported"),
+        }
+    }
+}
+
+impl From<&str> for Order {
+    fn from(i: &str) -> Self {
+        match i.to_lowercase().as_str() {
+            ":asc" => Order::Asc,
+            ":desc" => Order::Desc,
+            _ => unimplemented!("no other order supported"),
+        }
+    }
+}
+
 // impl std::str::FromStr for Wql {
 //     type Err = String;
 
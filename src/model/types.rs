@@ -7,7 +7,7 @@ use nom::sequence::preceded;
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::{cmp::Ordering, hash::Hash};
+use std::{cmp::Ordering, convert::TryFrom, hash::Hash};
 use uuid::Uuid;
 
 use crate::logic::integer_decode;
@@ -15,9 +15,9 @@ use crate::parser::types::{
     boolean, char_parse, datetime_parser, integer, precise_number_parser, sp, vector,
 };
 use crate::parser::types::{hashmap, string};
-use crate::parser::types::{nil, uuid_parser};
+use crate::parser::types::{nil, placeholder, uuid_parser};
 
-#[allow(clippy::derive_hash_xor_eq)]
+#[allow(clippy::derived_hash_with_manual_eq)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Types {
     Char(char),
@@ -32,12 +32,52 @@ pub enum Types {
     Precise(String),
     DateTime(DateTime<Utc>),
     Nil(Nil),
+    Placeholder(Placeholder), // only in `PreparedWql`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SchemaType {
+    Char,
+    Integer,
+    String,
+    Uuid,
+    Float,
+    Boolean,
+    Vector,
+    Map,
+    Hash,
+    Precise,
+    DateTime,
+    Nil,
+}
+
+impl TryFrom<&str> for SchemaType {
+    type Error = String;
+
+    fn try_from(i: &str) -> Result<Self, Self::Error> {
+        match i {
+            "Char" => Ok(SchemaType::Char),
+            "Integer" => Ok(SchemaType::Integer),
+            "String" => Ok(SchemaType::String),
+            "Uuid" => Ok(SchemaType::Uuid),
+            "Float" => Ok(SchemaType::Float),
+            "Boolean" => Ok(SchemaType::Boolean),
+            "Vector" => Ok(SchemaType::Vector),
+            "Map" => Ok(SchemaType::Map),
+            "Hash" => Ok(SchemaType::Hash),
+            "Precise" => Ok(SchemaType::Precise),
+            "DateTime" => Ok(SchemaType::DateTime),
+            "Nil" => Ok(SchemaType::Nil),
+            _ => Err(format!("`{}` is not a Types variant", i)),
+        }
+    }
 }
 
 pub fn wql_value(input: &str) -> IResult<&str, Types, VerboseError<&str>> {
     preceded(
         sp,
         alt((
+            map(placeholder, Types::Placeholder),
             map(hashmap, Types::Map),
             map(uuid_parser, Types::Uuid),
             map(string, Types::String),
@@ -56,6 +96,79 @@ pub fn wql_value(input: &str) -> IResult<&str, Types, VerboseError<&str>> {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Nil;
 
+// `$1`, `:name` or a `$name` script variable
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Placeholder {
+    Index(usize),
+    Name(String),
+    Variable(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ContentOp {
+    Set(Types),
+    Add(Types),
+    Append(Types),
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl ContentOp {
+    pub(crate) fn try_map<F>(self, f: &mut F) -> Result<ContentOp, String>
+    where
+        F: FnMut(Types) -> Result<Types, String>,
+    {
+        Ok(match self {
+            ContentOp::Set(t) => ContentOp::Set(t.try_map(f)?),
+            // mapped placeholders must still be numbers
+            ContentOp::Add(t) => match t.try_map(f)? {
+                t @ Types::Integer(_)
+                | t @ Types::Float(_)
+                | t @ Types::Precise(_)
+                | t @ Types::Placeholder(_) => ContentOp::Add(t),
+                t => return Err(format!("Only numbers can be added, found {:?}", t)),
+            },
+            ContentOp::Append(t) => ContentOp::Append(t.try_map(f)?),
+            ContentOp::Remove => ContentOp::Remove,
+        })
+    }
+}
+
+pub(crate) fn try_map_content<F>(
+    content: HashMap<String, Types>,
+    f: &mut F,
+) -> Result<HashMap<String, Types>, String>
+where
+    F: FnMut(Types) -> Result<Types, String>,
+{
+    content
+        .into_iter()
+        .map(|(k, v)| v.try_map(f).map(|v| (k, v)))
+        .collect()
+}
+
+impl PathSegment {
+    // `address.city` -> [Key("address"), Key("city")], `tags[0]` -> [Key("tags"), Index(0)]
+    pub fn from_key(key: &str) -> Result<Vec<PathSegment>, String> {
+        key.split('.')
+            .flat_map(|part| part.split('['))
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment.strip_suffix(']') {
+                Some(index) => index
+                    .parse::<usize>()
+                    .map(PathSegment::Index)
+                    .map_err(|e| format!("Invalid index `{}` in `{}`: {:?}", index, key, e)),
+                None => Ok(PathSegment::Key(segment.to_string())),
+            })
+            .collect()
+    }
+}
+
 impl Types {
     pub fn default_values(&self) -> Types {
         match self {
@@ -71,12 +184,30 @@ impl Types {
             Types::Precise(_) => Types::Precise(String::from("0")),
             Types::DateTime(_) => Types::DateTime(Utc::now()),
             Types::Nil(Nil) => Types::Nil(Nil),
+            Types::Placeholder(p) => Types::Placeholder(p.clone()),
         }
     }
 
     pub fn to_hash(&self, cost: Option<u32>) -> Result<Types, String> {
         use bcrypt::{hash, DEFAULT_COST};
-        let value = match self {
+        let value = self.hashable_value()?;
+        match hash(&value, cost.map_or(DEFAULT_COST, |c| c)) {
+            Ok(s) => Ok(Types::Hash(s)),
+            Err(e) => Err(format!("{:?}", e)),
+        }
+    }
+
+    pub fn check_hash(&self, hashed: &Types) -> Result<bool, String> {
+        use bcrypt::verify;
+        let value = self.hashable_value()?;
+        match hashed {
+            Types::Hash(h) => verify(&value, h).map_err(|e| format!("{:?}", e)),
+            _ => Err(String::from("Only Hash can be checked")),
+        }
+    }
+
+    fn hashable_value(&self) -> Result<String, String> {
+        Ok(match self {
             Types::Char(c) => format!("{}", c),
             Types::Integer(i) => format!("{}", i),
             Types::String(s) => s.to_string(),
@@ -89,13 +220,72 @@ impl Types {
             Types::Precise(p) => p.to_string(),
             Types::Hash(_) => return Err(String::from("Hash cannot be hashed")),
             Types::Nil(_) => return Err(String::from("Nil cannot be hashed")),
-        };
-        match hash(&value, cost.map_or(DEFAULT_COST, |c| c)) {
-            Ok(s) => Ok(Types::Hash(s)),
-            Err(e) => Err(format!("{:?}", e)),
+            Types::Placeholder(_) => return Err(String::from("Placeholder cannot be hashed")),
+        })
+    }
+
+    // Placeholders are not values yet, so they have no schema type
+    pub fn schema_type(&self) -> Option<SchemaType> {
+        Some(match self {
+            Types::Char(_) => SchemaType::Char,
+            Types::Integer(_) => SchemaType::Integer,
+            Types::String(_) => SchemaType::String,
+            Types::Uuid(_) => SchemaType::Uuid,
+            Types::Float(_) => SchemaType::Float,
+            Types::Boolean(_) => SchemaType::Boolean,
+            Types::Vector(_) => SchemaType::Vector,
+            Types::Map(_) => SchemaType::Map,
+            Types::Hash(_) => SchemaType::Hash,
+            Types::Precise(_) => SchemaType::Precise,
+            Types::DateTime(_) => SchemaType::DateTime,
+            Types::Nil(_) => SchemaType::Nil,
+            Types::Placeholder(_) => return None,
+        })
+    }
+
+    // Applies `f` to every value that is not a `Vector` or a `Map`
+    pub(crate) fn try_map<F>(self, f: &mut F) -> Result<Types, String>
+    where
+        F: FnMut(Types) -> Result<Types, String>,
+    {
+        match self {
+            Types::Vector(vec) => vec
+                .into_iter()
+                .map(|t| t.try_map(f))
+                .collect::<Result<_, _>>()
+                .map(Types::Vector),
+            Types::Map(map) => try_map_content(map, f).map(Types::Map),
+            t => f(t),
         }
     }
 
+    // Missing map keys along the path are created, vector indexes must already exist
+    pub fn set_path(&mut self, path: &[PathSegment], value: Types) -> Result<(), String> {
+        let (segment, rest) = match path.split_first() {
+            Some(split) => split,
+            None => {
+                *self = value;
+                return Ok(());
+            }
+        };
+        let inner = match (self, segment) {
+            (Types::Map(map), PathSegment::Key(key)) => {
+                map.entry(key.to_owned())
+                    .or_insert_with(|| match rest.first() {
+                        Some(PathSegment::Index(_)) => Types::Vector(Vec::new()),
+                        _ => Types::Map(HashMap::new()),
+                    })
+            }
+            (Types::Vector(vec), PathSegment::Index(index)) => {
+                let len = vec.len();
+                vec.get_mut(*index)
+                    .ok_or_else(|| format!("Index {} out of bounds for length {}", index, len))?
+            }
+            (types, segment) => return Err(format!("Can't access {:?} in {:?}", segment, types)),
+        };
+        inner.set_path(rest, value)
+    }
+
     pub fn is_hash(&self) -> bool {
         matches!(self, Types::Hash(_))
     }
@@ -135,7 +325,7 @@ impl PartialOrd for Types {
 }
 
 // UNSAFE
-#[allow(clippy::derive_hash_xor_eq)] // for now
+#[allow(clippy::derived_hash_with_manual_eq)] // for now
 impl Hash for Types {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
@@ -158,6 +348,7 @@ impl Hash for Types {
             Types::Precise(t) => t.hash(state),
             Types::DateTime(t) => t.hash(state),
             Types::Nil(_) => "".hash(state),
+            Types::Placeholder(t) => t.hash(state),
         }
     }
 }
@@ -240,7 +431,7 @@ mod tests {
             Ok((
                 "",
                 Types::Map(
-                    vec![(
+                    [(
                         "a".to_owned(),
                         Types::Uuid(
                             Uuid::from_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap()
@@ -261,10 +452,10 @@ mod tests {
             Ok((
                 "",
                 Types::Map(
-                    vec![(
+                    [(
                         "a".to_owned(),
                         Types::Map(
-                            vec![
+                            [
                                 (
                                     "b".to_owned(),
                                     Types::Uuid(
@@ -294,7 +485,7 @@ mod tests {
             Ok((
                 "",
                 Types::Map(
-                    vec![
+                    [
                         (
                             "a".to_owned(),
                             Types::Uuid(
@@ -315,6 +506,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_hash() {
+        let hashed = Types::String("s3cret".to_string())
+            .to_hash(Some(4))
+            .unwrap();
+
+        assert_eq!(
+            Ok(true),
+            Types::String("s3cret".to_string()).check_hash(&hashed)
+        );
+        assert_eq!(
+            Ok(false),
+            Types::String("secret".to_string()).check_hash(&hashed)
+        );
+        assert!(Types::Nil(Nil).check_hash(&hashed).is_err());
+    }
+
+    #[test]
+    fn set_path() {
+        let mut user = wql_value("{address: {city: \"Busan\"}, tags: [\"new\", \"old\"]}")
+            .unwrap()
+            .1;
+
+        user.set_path(
+            &PathSegment::from_key("address.city").unwrap(),
+            Types::from("Seoul"),
+        )
+        .unwrap();
+        user.set_path(
+            &PathSegment::from_key("tags[0]").unwrap(),
+            Types::from("vip"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            wql_value("{address: {city: \"Seoul\"}, tags: [\"vip\", \"old\"]}")
+                .unwrap()
+                .1,
+            user
+        );
+        assert!(user
+            .set_path(&PathSegment::from_key("tags[2]").unwrap(), Types::from("x"))
+            .is_err());
+        assert!(user
+            .set_path(
+                &PathSegment::from_key("address[0]").unwrap(),
+                Types::from("x")
+            )
+            .is_err());
+    }
+
     #[test]
     fn float_vectors() {
         assert_eq!(
@@ -0,0 +1,210 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::char,
+    combinator::{cut, map},
+    error::{context, VerboseError},
+    multi::separated_list1,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::model::{
+    clauses::{Clause, Comparison},
+    types::{wql_value, Types},
+};
+
+use super::types::{alphanumerickey1, sp, string, vector};
+
+// `{age >= 18i, name like "%ana%", or(city in ["Seoul"], not(age between 1i and 9i))}`
+// Top level clauses are joined by `and`.
+pub fn clauses(input: &str) -> IResult<&str, Vec<Clause>, VerboseError<&str>> {
+    context(
+        "clauses",
+        preceded(
+            char('{'),
+            cut(terminated(
+                separated_list1(preceded(sp, char(',')), clause),
+                alt((tag("}"), tag(",}"), preceded(sp, tag("}")))),
+            )),
+        ),
+    )(input)
+}
+
+pub fn clause(input: &str) -> IResult<&str, Clause, VerboseError<&str>> {
+    preceded(
+        sp,
+        alt((
+            map(clause_group("and"), Clause::And),
+            map(clause_group("or"), Clause::Or),
+            map(clause_group("not"), |mut clauses| {
+                if clauses.len() == 1 {
+                    Clause::Not(Box::new(clauses.remove(0)))
+                } else {
+                    Clause::Not(Box::new(Clause::And(clauses)))
+                }
+            }),
+            predicate,
+        )),
+    )(input)
+}
+
+pub fn clause_group<'a>(
+    name: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Clause>, VerboseError<&'a str>> {
+    preceded(
+        tuple((tag_no_case(name), sp, char('('))),
+        cut(terminated(
+            separated_list1(preceded(sp, char(',')), clause),
+            alt((tag(")"), tag(",)"), preceded(sp, tag(")")))),
+        )),
+    )
+}
+
+fn predicate(input: &str) -> IResult<&str, Clause, VerboseError<&str>> {
+    context(
+        "predicate",
+        tuple((
+            alphanumerickey1,
+            preceded(
+                sp,
+                alt((
+                    map(tuple((comparison, wql_value)), |(op, value)| {
+                        Predicate::Compare(op, value)
+                    }),
+                    map(
+                        preceded(tuple((tag_no_case("in"), sp)), cut(vector)),
+                        Predicate::In,
+                    ),
+                    map(
+                        preceded(
+                            tag_no_case("between"),
+                            cut(tuple((
+                                wql_value,
+                                delimited(sp, tag_no_case("and"), sp),
+                                wql_value,
+                            ))),
+                        ),
+                        |(start, _, end)| Predicate::Between(start, end),
+                    ),
+                    map(
+                        preceded(tuple((tag_no_case("like"), sp)), cut(string)),
+                        Predicate::Like,
+                    ),
+                )),
+            ),
+        )),
+    )(input)
+    .map(|(next, (key, predicate))| {
+        let key = key.to_string();
+        let clause = match predicate {
+            Predicate::Compare(op, value) => Clause::Compare(key, op, value),
+            Predicate::In(values) => Clause::In(key, values),
+            Predicate::Between(start, end) => Clause::Between(key, start, end),
+            Predicate::Like(pattern) => Clause::Like(key, pattern),
+        };
+        (next, clause)
+    })
+}
+
+fn comparison(input: &str) -> IResult<&str, Comparison, VerboseError<&str>> {
+    context(
+        "comparison",
+        alt((
+            tag("=="),
+            tag("!="),
+            tag(">="),
+            tag("<="),
+            tag(">"),
+            tag("<"),
+        )),
+    )(input)
+    .map(|(next_input, res)| (next_input, res.into()))
+}
+
+enum Predicate {
+    Compare(Comparison, Types),
+    In(Vec<Types>),
+    Between(Types, Types),
+    Like(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison_clauses() {
+        assert_eq!(
+            Ok((
+                "",
+                vec![
+                    Clause::Compare(
+                        "age".to_string(),
+                        Comparison::GreaterOrEqual,
+                        Types::Integer(18)
+                    ),
+                    Clause::Compare(
+                        "name".to_string(),
+                        Comparison::NotEqual,
+                        Types::String("ana".to_string())
+                    ),
+                ]
+            )),
+            clauses("{ age >= 18i, name != \"ana\" }")
+        );
+    }
+
+    #[test]
+    fn in_between_like_clauses() {
+        assert_eq!(
+            Ok((
+                "",
+                vec![
+                    Clause::In(
+                        "city".to_string(),
+                        vec![
+                            Types::String("Seoul".to_string()),
+                            Types::String("Busan".to_string())
+                        ]
+                    ),
+                    Clause::Between("age".to_string(), Types::Integer(18), Types::Integer(30)),
+                    Clause::Like("name".to_string(), "%ana%".to_string()),
+                ]
+            )),
+            clauses(
+                "{city in [\"Seoul\", \"Busan\"], age between 18i and 30i, name like \"%ana%\",}"
+            )
+        );
+    }
+
+    #[test]
+    fn grouped_clauses() {
+        assert_eq!(
+            Ok((
+                "",
+                vec![Clause::Or(vec![
+                    Clause::Compare("age".to_string(), Comparison::Lower, Types::Integer(18)),
+                    Clause::And(vec![
+                        Clause::Compare(
+                            "android".to_string(),
+                            Comparison::Equal,
+                            Types::Boolean(true)
+                        ),
+                        Clause::Not(Box::new(Clause::Like(
+                            "name".to_string(),
+                            "%ana%".to_string()
+                        ))),
+                    ]),
+                ])]
+            )),
+            clauses("{or(age < 18i, and(android == true, not(name like \"%ana%\")))}")
+        );
+    }
+
+    #[test]
+    fn invalid_clause() {
+        assert!(clauses("{ age >> 18i }").is_err());
+        assert!(clauses("{ age between 18i 30i }").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::model::{CreateOptions, Operation};
+use crate::model::{CreateOptions, Operation, Order, RelationMode, RelationType};
 use nom::{
     branch::alt,
     bytes::streaming::tag_no_case,
@@ -12,6 +12,34 @@ const WITH: &str = "WITH";
 pub const SET: &str = "SET";
 pub const CONTENT: &str = "CONTENT";
 const FROM: &str = "FROM";
+const ID: &str = "ID";
+const IDS: &str = "IDS";
+const IN: &str = "IN";
+const WHERE: &str = "WHERE";
+const WHEN: &str = "WHEN";
+const AT: &str = "AT";
+const START: &str = "START";
+const END: &str = "END";
+const ORDER: &str = "ORDER";
+const GROUP: &str = "GROUP";
+const BY: &str = "BY";
+const LIMIT: &str = "LIMIT";
+const OFFSET: &str = "OFFSET";
+const DEDUP: &str = "DEDUP";
+const COUNT: &str = "COUNT";
+const UPDATE: &str = "UPDATE";
+const ON: &str = "ON";
+const CONFLICT: &str = "CONFLICT";
+const DO: &str = "DO";
+const NOTHING: &str = "NOTHING";
+const SCHEMA: &str = "SCHEMA";
+const ADD: &str = "ADD";
+const DROP: &str = "DROP";
+const ENTITIES: &str = "ENTITIES";
+const LET: &str = "LET";
+const IF: &str = "IF";
+const NOT: &str = "NOT";
+const EXISTS: &str = "EXISTS";
 
 pub fn operation(input: &str) -> IResult<&str, Operation, VerboseError<&str>> {
     context(
@@ -27,6 +55,13 @@ pub fn operation(input: &str) -> IResult<&str, Operation, VerboseError<&str>> {
             tag_no_case("CHECK"),
             tag_no_case("RELATION"),
             tag_no_case("JOIN"),
+            tag_no_case("ALTER"),
+            tag_no_case("SHOW"),
+            tag_no_case("DESCRIBE"),
+            tag_no_case("DROP"),
+            tag_no_case("BEGIN"),
+            tag_no_case("COMMIT"),
+            tag_no_case("EXPLAIN"),
         )),
     )(input)
     .map(|(next_input, res)| (next_input, res.into()))
@@ -39,6 +74,41 @@ pub fn entity(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
     })
 }
 
+pub fn entities(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("entities", tag_no_case(ENTITIES))(input).and_then(|(next_input, res)| match res {
+        ENTITIES => Ok((next_input, ENTITIES)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn let_keyword(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("let", tag_no_case(LET))(input).and_then(|(next_input, res)| match res {
+        LET => Ok((next_input, LET)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn if_keyword(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("if", tag_no_case(IF))(input).and_then(|(next_input, res)| match res {
+        IF => Ok((next_input, IF)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn not(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("not", tag_no_case(NOT))(input).and_then(|(next_input, res)| match res {
+        NOT => Ok((next_input, NOT)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn exists(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("exists", tag_no_case(EXISTS))(input).and_then(|(next_input, res)| match res {
+        EXISTS => Ok((next_input, EXISTS)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
 pub fn into(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
     context("into", tag_no_case(INTO))(input).and_then(|(next_input, res)| match res {
         INTO => Ok((next_input, INTO)),
@@ -74,6 +144,175 @@ pub fn from(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
     })
 }
 
+pub fn id(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("id", tag_no_case(ID))(input).and_then(|(next_input, res)| match res {
+        ID => Ok((next_input, ID)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn ids(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("ids", tag_no_case(IDS))(input).and_then(|(next_input, res)| match res {
+        IDS => Ok((next_input, IDS)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn in_keyword(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("in", tag_no_case(IN))(input).and_then(|(next_input, res)| match res {
+        IN => Ok((next_input, IN)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn where_keyword(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("where", tag_no_case(WHERE))(input).and_then(|(next_input, res)| match res {
+        WHERE => Ok((next_input, WHERE)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn when(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("when", tag_no_case(WHEN))(input).and_then(|(next_input, res)| match res {
+        WHEN => Ok((next_input, WHEN)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn at(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("at", tag_no_case(AT))(input).and_then(|(next_input, res)| match res {
+        AT => Ok((next_input, AT)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn start(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("start", tag_no_case(START))(input).and_then(|(next_input, res)| match res {
+        START => Ok((next_input, START)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn end(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("end", tag_no_case(END))(input).and_then(|(next_input, res)| match res {
+        END => Ok((next_input, END)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn order(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("order", tag_no_case(ORDER))(input).and_then(|(next_input, res)| match res {
+        ORDER => Ok((next_input, ORDER)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn group(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("group", tag_no_case(GROUP))(input).and_then(|(next_input, res)| match res {
+        GROUP => Ok((next_input, GROUP)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn by(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("by", tag_no_case(BY))(input).and_then(|(next_input, res)| match res {
+        BY => Ok((next_input, BY)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn limit(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("limit", tag_no_case(LIMIT))(input).and_then(|(next_input, res)| match res {
+        LIMIT => Ok((next_input, LIMIT)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn offset(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("offset", tag_no_case(OFFSET))(input).and_then(|(next_input, res)| match res {
+        OFFSET => Ok((next_input, OFFSET)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn dedup(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("dedup", tag_no_case(DEDUP))(input).and_then(|(next_input, res)| match res {
+        DEDUP => Ok((next_input, DEDUP)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn count(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("count", tag_no_case(COUNT))(input).and_then(|(next_input, res)| match res {
+        COUNT => Ok((next_input, COUNT)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn update(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("update", tag_no_case(UPDATE))(input).and_then(|(next_input, res)| match res {
+        UPDATE => Ok((next_input, UPDATE)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn on(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("on", tag_no_case(ON))(input).and_then(|(next_input, res)| match res {
+        ON => Ok((next_input, ON)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn conflict(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("conflict", tag_no_case(CONFLICT))(input).and_then(|(next_input, res)| match res {
+        CONFLICT => Ok((next_input, CONFLICT)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn do_keyword(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("do", tag_no_case(DO))(input).and_then(|(next_input, res)| match res {
+        DO => Ok((next_input, DO)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn nothing(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("nothing", tag_no_case(NOTHING))(input).and_then(|(next_input, res)| match res {
+        NOTHING => Ok((next_input, NOTHING)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn schema(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("schema", tag_no_case(SCHEMA))(input).and_then(|(next_input, res)| match res {
+        SCHEMA => Ok((next_input, SCHEMA)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn add(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("add", tag_no_case(ADD))(input).and_then(|(next_input, res)| match res {
+        ADD => Ok((next_input, ADD)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn drop(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context("drop", tag_no_case(DROP))(input).and_then(|(next_input, res)| match res {
+        DROP => Ok((next_input, DROP)),
+        _ => Err(NomErr::Error(VerboseError { errors: vec![] })),
+    })
+}
+
+pub fn order_direction(input: &str) -> IResult<&str, Order, VerboseError<&str>> {
+    context(
+        "order_direction",
+        alt((tag_no_case(":asc"), tag_no_case(":desc"))),
+    )(input)
+    .map(|(next_input, res)| (next_input, res.into()))
+}
+
 pub fn create_options(input: &str) -> IResult<&str, CreateOptions, VerboseError<&str>> {
     context(
         "create_options",
@@ -82,6 +321,26 @@ pub fn create_options(input: &str) -> IResult<&str, CreateOptions, VerboseError<
     .map(|(next_input, res)| (next_input, res.into()))
 }
 
+pub fn relation_type(input: &str) -> IResult<&str, RelationType, VerboseError<&str>> {
+    context(
+        "relation_type",
+        alt((
+            tag_no_case("INTERSECT"),
+            tag_no_case("DIFFERENCE"),
+            tag_no_case("UNION"),
+        )),
+    )(input)
+    .map(|(next_input, res)| (next_input, res.into()))
+}
+
+pub fn relation_mode(input: &str) -> IResult<&str, RelationMode, VerboseError<&str>> {
+    context(
+        "relation_mode",
+        alt((tag_no_case("KEY"), tag_no_case("HASH"))),
+    )(input)
+    .map(|(next_input, res)| (next_input, res.into()))
+}
+
 #[cfg(test)]
 mod operation_test {
     use nom::error::{ErrorKind, VerboseErrorKind};
@@ -188,6 +447,30 @@ mod operation_test {
         )
     }
 
+    #[test]
+    fn alter() {
+        assert_eq!(
+            operation("ALTER and some random string after"),
+            Ok((" and some random string after", Operation::ALTER))
+        )
+    }
+
+    #[test]
+    fn admin() {
+        assert_eq!(
+            operation("SHOW and some random string after"),
+            Ok((" and some random string after", Operation::SHOW))
+        );
+        assert_eq!(
+            operation("describe and some random string after"),
+            Ok((" and some random string after", Operation::DESCRIBE))
+        );
+        assert_eq!(
+            operation("DROP and some random string after"),
+            Ok((" and some random string after", Operation::DROP))
+        );
+    }
+
     #[test]
     fn entity_test() {
         assert_eq!(
@@ -242,4 +525,16 @@ mod operation_test {
             }))
         );
     }
+
+    #[test]
+    fn relation_test() {
+        assert_eq!(
+            relation_type("Difference and some random string after"),
+            Ok((" and some random string after", RelationType::Difference))
+        );
+        assert_eq!(
+            relation_mode("hash and some random string after"),
+            Ok((" and some random string after", RelationMode::Hash))
+        );
+    }
 }
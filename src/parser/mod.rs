@@ -1,43 +1,163 @@
-use nom::error::context;
+use std::collections::HashMap;
+
+use nom::{
+    character::complete::{char, satisfy},
+    combinator::not,
+    error::{context, VerboseError},
+    sequence::{preceded, terminated},
+    IResult,
+};
 use uuid::Uuid;
 
+use crate::logic::split_top_level;
 use crate::model::error::WqlError;
-use crate::model::Operation;
+use crate::model::{
+    types::{Placeholder, Types},
+    Operation, PreparedWql, SelectLookup, UpdatePayload, When,
+};
+use crate::parser::operation_content::alter_content;
+use crate::parser::operation_content::check_content;
 use crate::parser::operation_content::delete_content;
+use crate::parser::operation_content::describe_content;
+use crate::parser::operation_content::drop_content;
 use crate::parser::operation_content::evict_content;
+use crate::parser::operation_content::evict_keys_content;
 use crate::parser::operation_content::insert_content;
+use crate::parser::operation_content::insert_many_content;
+use crate::parser::operation_content::join_content;
+use crate::parser::operation_content::match_update_content;
+use crate::parser::operation_content::relation_content;
+use crate::parser::operation_content::select_content;
+use crate::parser::operation_content::show_content;
 use crate::parser::operation_content::update_content;
 use crate::{
     model::Wql,
     parser::{
-        keywords::{operation, CONTENT, SET},
+        keywords::{let_keyword, operation},
         operation_content::create_content,
+        types::{alphanumerickey1, is_blank, is_key_char, sp},
     },
 };
 
+pub mod clauses;
 pub mod keywords;
 pub mod operation_content;
 pub mod types;
 
-pub fn parse_wql(input: &str) -> Result<Wql, WqlError> {
-    context("parse_wql", operation)(input)
+pub fn parse_wql(input: &str) -> Result<Wql, WqlError<'_>> {
+    parse_statement(input).and_then(|wql| without_placeholders(wql, input))
+}
+
+// Same as `parse_wql`, but values can be `$1` or `:name` placeholders to `bind` later
+pub fn prepare_wql(input: &str) -> Result<PreparedWql, WqlError<'_>> {
+    parse_statement(input).map(PreparedWql::new)
+}
+
+fn without_placeholders<'a>(wql: Wql, input: &str) -> Result<Wql, WqlError<'a>> {
+    wql.try_map_values(&mut |value| placeholder_error(value, input))
+        .map_err(WqlError::Plain)
+}
+
+fn placeholder_error(value: Types, input: &str) -> Result<Types, String> {
+    match value {
+        Types::Placeholder(Placeholder::Variable(variable)) => Err(format!(
+            "Couldn't parse input `{}`.\n Parsing error: variable `${}` is only available in scripts",
+            input, variable
+        )),
+        Types::Placeholder(placeholder) => Err(format!(
+            "Couldn't parse input `{}`.\n Parsing error: placeholder {:?} requires `prepare_wql`",
+            input, placeholder
+        )),
+        value => Ok(value),
+    }
+}
+
+fn parse_statement(input: &str) -> Result<Wql, WqlError<'_>> {
+    context("parse_wql", preceded(sp, operation))(input)
         .map(|(next, op)| match op {
             Operation::CREATE => match create_content(next) {
-                Ok((name, (uniques, encrypts))) => Ok(Wql::CreateEntity {
+                Ok((name, (if_not_exists, uniques, encrypts, schema))) => Ok(Wql::CreateEntity {
                     name: name.to_owned(),
                     uniques,
                     encrypts,
+                    schema,
+                    if_not_exists,
                 }),
                 Err(e) => Err(WqlError::Plain(format!(
                     "Couldn't parse input `{}` as CREATE.\n Parsing error: {:?}",
                     input, e
                 ))),
             },
+            Operation::ALTER => match alter_content(next) {
+                Ok((name, changes)) => Ok(Wql::AlterEntity {
+                    name: name.to_owned(),
+                    changes,
+                }),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as ALTER.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::SHOW => match show_content(next) {
+                Ok(_) => Ok(Wql::ShowEntities),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as SHOW.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::DESCRIBE => match describe_content(next) {
+                Ok((entity, _)) => Ok(Wql::Describe {
+                    entity: entity.to_owned(),
+                }),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as DESCRIBE.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::DROP => match drop_content(next) {
+                Ok((name, _)) => Ok(Wql::DropEntity {
+                    name: name.to_owned(),
+                }),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as DROP.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::BEGIN => transaction(next, input),
+            Operation::EXPLAIN => parse_statement(next).map(|wql| Wql::Explain(Box::new(wql))),
+            Operation::COMMIT => Err(WqlError::Plain(format!(
+                "Couldn't parse input `{}` as COMMIT.\n Parsing error: {:?}",
+                input, "COMMIT without a matching BEGIN"
+            ))),
+            Operation::INSERT if next.trim_start().starts_with('[') => {
+                match insert_many_content(next) {
+                    Ok((entity, contents)) => Ok(Wql::InsertMany {
+                        entity: entity.to_string(),
+                        contents,
+                    }),
+                    Err(e) => Err(WqlError::Plain(format!(
+                        "Couldn't parse input `{}` as INSERT.\n Parsing error: {:?}",
+                        input, e
+                    ))),
+                }
+            }
             Operation::INSERT => match insert_content(next) {
-                Ok((entity, (content, id))) => Ok(Wql::Insert {
+                Ok((entity, (content, id, if_not_exists, None))) => Ok(Wql::Insert {
                     entity: entity.to_string(),
                     content,
                     id,
+                    if_not_exists,
+                }),
+                Ok((_, (_, _, true, Some(_)))) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as INSERT.\n Parsing error: {:?}",
+                    input, "IF NOT EXISTS can't be combined with ON CONFLICT"
+                ))),
+                Ok((entity, (content, id, false, Some((conflict, action))))) => Ok(Wql::Upsert {
+                    entity: entity.to_string(),
+                    content,
+                    id,
+                    conflict,
+                    action,
                 }),
                 Err(e) => Err(WqlError::Plain(format!(
                     "Couldn't parse input `{}` as INSERT.\n Parsing error: {:?}",
@@ -45,35 +165,44 @@ pub fn parse_wql(input: &str) -> Result<Wql, WqlError> {
                 ))),
             },
             Operation::UPDATE => match update_content(next) {
-                Ok((entity, (update_type, content, id))) => match update_type {
-                    CONTENT => Ok(Wql::UpdateContent {
-                        name: entity.to_string(),
-                        id,
-                        content,
-                    }),
-                    SET => Ok(Wql::UpdateSet {
-                        name: entity.to_string(),
-                        id,
-                        content,
-                    }),
-                    _ => Err(WqlError::Plain(format!(
-                        "Couldn't parse input `{}` as UPDATE.\n Parsing error: {:?}",
-                        input, "UPDATE type not found"
-                    ))),
-                },
+                Ok((entity, (UpdatePayload::Content(content), id))) => Ok(Wql::UpdateContent {
+                    name: entity.to_string(),
+                    id,
+                    content,
+                }),
+                Ok((entity, (UpdatePayload::Set(content), id))) => Ok(Wql::UpdateSet {
+                    name: entity.to_string(),
+                    id,
+                    content,
+                }),
                 Err(e) => Err(WqlError::Plain(format!(
                     "Couldn't parse input `{}` as UPDATE.\n Parsing error: {:?}",
                     input, e
                 ))),
             },
+            Operation::EVICT if next.trim_start().starts_with("#{") => {
+                match evict_keys_content(next) {
+                    Ok((entity, (keys, id))) => Ok(Wql::Evict {
+                        entity: entity.to_string(),
+                        id: Some(id),
+                        keys: Some(keys),
+                    }),
+                    Err(e) => Err(WqlError::Plain(format!(
+                        "Couldn't parse input `{}` as EVICT.\n Parsing error: {:?}",
+                        input, e
+                    ))),
+                }
+            }
             Operation::EVICT => match evict_content(next) {
                 Ok((entity_id, None)) => Ok(Wql::Evict {
                     entity: entity_id.to_string(),
                     id: None,
+                    keys: None,
                 }),
                 Ok((entity_id, Some(entity))) => Ok(Wql::Evict {
                     entity: entity.to_string(),
                     id: Some(Uuid::parse_str(entity_id)?),
+                    keys: None,
                 }),
                 Err(e) => Err(WqlError::Plain(format!(
                     "Couldn't parse input `{}` as EVICT.\n Parsing error: {:?}",
@@ -81,16 +210,373 @@ pub fn parse_wql(input: &str) -> Result<Wql, WqlError> {
                 ))),
             },
             Operation::DELETE => match delete_content(next) {
-                Ok((entity, id)) => Ok(Wql::Delete {
+                Ok((entity, (id, at))) => Ok(Wql::Delete {
                     entity: entity.to_string(),
                     id,
+                    at,
                 }),
                 Err(e) => Err(WqlError::Plain(format!(
                     "Couldn't parse input `{}` as Delete.\n Parsing error: {:?}",
                     input, e
                 ))),
             },
-            _ => unimplemented!(),
+            Operation::SELECT => match select_content(next) {
+                Ok((entity, (arg, None, None, functions))) => Ok(Wql::Select {
+                    entity: entity.to_string(),
+                    arg,
+                    id: None,
+                    functions,
+                }),
+                Ok((entity, (arg, Some(SelectLookup::Id(id)), None, functions))) => {
+                    Ok(Wql::Select {
+                        entity: entity.to_string(),
+                        arg,
+                        id: Some(id),
+                        functions,
+                    })
+                }
+                Ok((entity, (arg, Some(SelectLookup::Ids(ids)), None, functions))) => {
+                    Ok(Wql::SelectIds {
+                        entity: entity.to_string(),
+                        arg,
+                        ids,
+                        functions,
+                    })
+                }
+                Ok((entity, (arg, Some(SelectLookup::Where(clauses)), None, functions))) => {
+                    Ok(Wql::SelectWhere {
+                        entity: entity.to_string(),
+                        arg,
+                        clauses,
+                        functions,
+                    })
+                }
+                Ok((_, (_, _, Some(_), functions))) if !functions.is_empty() => {
+                    Err(WqlError::Plain(format!(
+                        "Couldn't parse input `{}` as SELECT.\n Parsing error: {:?}",
+                        input, "WHEN doesn't support select functions"
+                    )))
+                }
+                Ok((entity, (arg, lookup, Some(when), _))) => {
+                    let id = match lookup {
+                        None => None,
+                        Some(SelectLookup::Id(id)) => Some(id),
+                        Some(_) => {
+                            return Err(WqlError::Plain(format!(
+                                "Couldn't parse input `{}` as SELECT.\n Parsing error: {:?}",
+                                input, "WHEN is only supported for SELECT by entity or ID"
+                            )))
+                        }
+                    };
+                    match when {
+                        When::At(time) => Ok(Wql::SelectWhen {
+                            entity: entity.to_string(),
+                            arg,
+                            id,
+                            time,
+                        }),
+                        When::Range(start, end) => Ok(Wql::SelectWhenRange {
+                            entity: entity.to_string(),
+                            arg,
+                            id,
+                            start,
+                            end,
+                        }),
+                    }
+                }
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as SELECT.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::CHECK => match check_content(next) {
+                Ok((entity, (content, id))) => Ok(Wql::Check {
+                    entity: entity.to_string(),
+                    id,
+                    content,
+                }),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as CHECK.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::MATCH_UPDATE => match match_update_content(next) {
+                Ok((entity, (conditions, UpdatePayload::Set(content), id))) => {
+                    Ok(Wql::MatchUpdate {
+                        entity: entity.to_string(),
+                        id,
+                        content,
+                        conditions,
+                    })
+                }
+                Ok(_) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as MATCH UPDATE.\n Parsing error: {:?}",
+                    input, "MATCH only supports UPDATE SET"
+                ))),
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as MATCH UPDATE.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::RELATION => match relation_content(next) {
+                Ok((queries, (operation, mode))) => match split_top_level(queries, ',')[..] {
+                    [first, second] => Ok(Wql::Relation {
+                        operation,
+                        mode,
+                        queries: (
+                            Box::new(select_query(first, input)?),
+                            Box::new(select_query(second, input)?),
+                        ),
+                    }),
+                    _ => Err(WqlError::Plain(format!(
+                        "Couldn't parse input `{}` as RELATION.\n Parsing error: {:?}",
+                        input, "RELATION requires exactly two SELECT queries"
+                    ))),
+                },
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as RELATION.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+            Operation::JOIN => match join_content(next) {
+                Ok((queries, ((entity_a, key_a), (entity_b, key_b)))) => {
+                    match split_top_level(queries, '|')[..] {
+                        [first, second] => {
+                            let first = select_query(first, input)?;
+                            let second = select_query(second, input)?;
+                            if select_entity(&first) != Some(entity_a)
+                                || select_entity(&second) != Some(entity_b)
+                            {
+                                return Err(WqlError::Plain(format!(
+                                    "Couldn't parse input `{}` as JOIN.\n Parsing error: {:?}",
+                                    input, "JOIN keys must follow the SELECT entities order"
+                                )));
+                            }
+                            Ok(Wql::Join {
+                                entities: (entity_a.to_string(), entity_b.to_string()),
+                                keys: (key_a.to_string(), key_b.to_string()),
+                                queries: (Box::new(first), Box::new(second)),
+                            })
+                        }
+                        _ => Err(WqlError::Plain(format!(
+                            "Couldn't parse input `{}` as JOIN.\n Parsing error: {:?}",
+                            input, "JOIN requires exactly two SELECT queries separated by `|`"
+                        ))),
+                    }
+                }
+                Err(e) => Err(WqlError::Plain(format!(
+                    "Couldn't parse input `{}` as JOIN.\n Parsing error: {:?}",
+                    input, e
+                ))),
+            },
+        })
+        .map_err(WqlError::Parse)?
+}
+
+// Statements are separated by top level `;`, trailing ones are allowed.
+// A `BEGIN; ...; COMMIT` block counts as a single statement, so `WqlError::Script`
+// holds the index of the failing statement in the resulting `Vec<Wql>`
+pub fn parse_wql_script(input: &str) -> Result<Vec<Wql>, WqlError<'_>> {
+    let mut variables = HashMap::new();
+    let mut queries = Vec::new();
+    let mut statements = statements(input);
+    while let Some(statement) = statements.next() {
+        let explained = match split_keyword(statement) {
+            (keyword, rest) if keyword == "EXPLAIN" => {
+                Some(sp::<VerboseError<&str>>(rest).map_or(rest, |(rest, _)| rest))
+            }
+            _ => None,
+        };
+        let query = if is_keyword_statement(explained.unwrap_or(statement), "BEGIN") {
+            transaction_queries(&mut statements, statement, &mut |query| {
+                script_statement(query, &mut variables)
+            })
+            .map(|queries| match explained {
+                Some(_) => Wql::Explain(Box::new(Wql::Transaction(queries))),
+                None => Wql::Transaction(queries),
+            })
+        } else {
+            script_statement(statement, &mut variables)
+        };
+        let index = queries.len();
+        queries.push(query.map_err(|e| WqlError::Script(index, Box::new(e)))?);
+    }
+    Ok(queries)
+}
+
+// `LET name = INSERT ...` binds the inserted id to `$name` for the following statements
+fn script_statement<'a>(
+    statement: &'a str,
+    variables: &mut HashMap<String, Uuid>,
+) -> Result<Wql, WqlError<'a>> {
+    let (query, name) = match let_binding(statement) {
+        Ok((query, name)) => (query, Some(name)),
+        Err(_) => (statement, None),
+    };
+    let wql = parse_statement(query)?
+        .try_map_values(&mut |value| match value {
+            Types::Placeholder(Placeholder::Variable(variable)) => variables
+                .get(&variable)
+                .map(|id| Types::Uuid(*id))
+                .ok_or_else(|| {
+                    format!(
+                        "Couldn't parse input `{}`.\n Parsing error: variable `${}` is not defined",
+                        statement, variable
+                    )
+                }),
+            value => placeholder_error(value, statement),
+        })
+        .map_err(WqlError::Plain)?;
+
+    match (name, wql) {
+        (None, wql) => Ok(wql),
+        (Some(name), _) if variables.contains_key(name) => Err(WqlError::Plain(format!(
+            "Couldn't parse input `{}`.\n Parsing error: variable `${}` is already defined",
+            statement, name
+        ))),
+        // the bound id would not be written when the row already exists
+        (
+            Some(_),
+            Wql::Insert {
+                if_not_exists: true,
+                ..
+            },
+        ) => Err(WqlError::Plain(format!(
+            "Couldn't parse input `{}`.\n Parsing error: LET can't capture the id of an INSERT ... IF NOT EXISTS",
+            statement
+        ))),
+        (
+            Some(name),
+            Wql::Insert {
+                entity,
+                content,
+                id,
+                if_not_exists,
+            },
+        ) => {
+            let id = id.unwrap_or_else(Uuid::new_v4);
+            variables.insert(name.to_string(), id);
+            Ok(Wql::Insert {
+                entity,
+                content,
+                id: Some(id),
+                if_not_exists,
+            })
+        }
+        (Some(_), _) => Err(WqlError::Plain(format!(
+            "Couldn't parse input `{}`.\n Parsing error: LET only captures the id of an INSERT",
+            statement
+        ))),
+    }
+}
+
+fn let_binding(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    preceded(
+        terminated(let_keyword, preceded(not(satisfy(is_key_char)), sp)),
+        terminated(alphanumerickey1, preceded(sp, char('='))),
+    )(input)
+}
+
+// Statements without surrounding whitespace and leading comments
+fn statements(input: &str) -> impl Iterator<Item = &str> {
+    split_top_level(input, ';')
+        .into_iter()
+        .filter(|statement| !is_blank(statement))
+        .map(|statement| {
+            sp::<VerboseError<&str>>(statement)
+                .map_or(statement, |(rest, _)| rest)
+                .trim_end()
         })
-        .map_err(|e| WqlError::Parse(e))?
+}
+
+// Uppercased first word of a statement and what follows it
+fn split_keyword(statement: &str) -> (String, &str) {
+    let (keyword, rest) = statement.split_at(
+        statement
+            .find(char::is_whitespace)
+            .unwrap_or(statement.len()),
+    );
+    (keyword.to_uppercase(), rest)
+}
+
+fn is_keyword_statement(statement: &str, keyword: &str) -> bool {
+    let (first, rest) = split_keyword(statement);
+    first == keyword && is_blank(rest)
+}
+
+// `body` is everything after `BEGIN`, expected as `; statement; ...; COMMIT`
+fn transaction<'a>(body: &'a str, input: &str) -> Result<Wql, WqlError<'a>> {
+    let body = body
+        .trim_start()
+        .strip_prefix(';')
+        .ok_or_else(|| transaction_error(input, "BEGIN must be followed by `;`"))?;
+
+    let mut statements = statements(body);
+    let queries = transaction_queries(&mut statements, input, &mut parse_statement)?;
+    match statements.next() {
+        Some(_) => Err(transaction_error(input, "statements found after COMMIT")),
+        None => Ok(Wql::Transaction(queries)),
+    }
+}
+
+// Consumes statements up to the `COMMIT` closing the current transaction
+fn transaction_queries<'a, F>(
+    statements: &mut impl Iterator<Item = &'a str>,
+    input: &str,
+    parse: &mut F,
+) -> Result<Vec<Wql>, WqlError<'a>>
+where
+    F: FnMut(&'a str) -> Result<Wql, WqlError<'a>>,
+{
+    let mut queries = Vec::new();
+    for statement in statements {
+        let (keyword, rest) = split_keyword(statement);
+        match keyword.as_str() {
+            "BEGIN" => {
+                return Err(transaction_error(
+                    input,
+                    "nested transactions are not supported",
+                ))
+            }
+            "COMMIT" if !is_blank(rest) => {
+                return Err(transaction_error(input, "COMMIT must be followed by `;`"))
+            }
+            "COMMIT" => return Ok(queries),
+            _ => queries.push(parse(statement)?),
+        }
+    }
+    Err(transaction_error(input, "BEGIN without a matching COMMIT"))
+}
+
+fn transaction_error<'a>(input: &str, reason: &str) -> WqlError<'a> {
+    WqlError::Plain(format!(
+        "Couldn't parse input `{}` as TRANSACTION.\n Parsing error: {:?}",
+        input, reason
+    ))
+}
+
+fn select_query<'a>(query: &'a str, input: &str) -> Result<Wql, WqlError<'a>> {
+    match parse_statement(query.trim())? {
+        wql @ Wql::Select { .. }
+        | wql @ Wql::SelectIds { .. }
+        | wql @ Wql::SelectWhere { .. }
+        | wql @ Wql::SelectWhen { .. }
+        | wql @ Wql::SelectWhenRange { .. } => Ok(wql),
+        _ => Err(WqlError::Plain(format!(
+            "Couldn't parse input `{}`.\n Parsing error: `{}` is not a SELECT",
+            input,
+            query.trim()
+        ))),
+    }
+}
+
+fn select_entity(wql: &Wql) -> Option<&str> {
+    match wql {
+        Wql::Select { entity, .. }
+        | Wql::SelectIds { entity, .. }
+        | Wql::SelectWhere { entity, .. }
+        | Wql::SelectWhen { entity, .. }
+        | Wql::SelectWhenRange { entity, .. } => Some(entity),
+        _ => None,
+    }
 }
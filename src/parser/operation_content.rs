@@ -1,68 +1,175 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use nom::{
     branch::alt,
-    error::VerboseError,
-    sequence::{delimited, preceded, tuple},
-    IResult,
+    character::complete::{char, satisfy},
+    combinator::{cut, map, not, verify},
+    error::{ErrorKind, ParseError, VerboseError},
+    multi::separated_list1,
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    Err as NomErr, IResult,
 };
 use uuid::Uuid;
 
 use crate::{
-    model::{types::Types, CreateOptions},
-    parser::keywords::{create_options, entity},
+    model::{
+        clauses::Clause,
+        types::{SchemaType, Types},
+        Alteration, ConflictAction, CreateOptions, Order, RelationMode, RelationType,
+        SelectFunction, SelectLookup, ToSelect, UpdatePayload, When,
+    },
+    parser::keywords::{create_options, entities, entity},
 };
 
 use crate::parser::{
     types::sp,
     {
         keywords::into,
-        types::{alphanumerickey1, hashmap},
+        types::{alphanumerickey1, content_op_map, hashmap, hashmap_vector, path_hashmap},
     },
 };
 
 use super::{
-    keywords::{content, from, set as keyword_set, with},
-    types::{alphanumericboth1, set, uuid_parser},
+    clauses::{clause_group, clauses},
+    keywords::{
+        add, at, by, conflict, content, count, dedup, do_keyword, drop, end, exists, from, group,
+        id, ids, if_keyword, in_keyword, limit, not as not_keyword, nothing, offset, on, order,
+        order_direction, relation_mode, relation_type, schema, set as keyword_set, start, update,
+        when, where_keyword, with,
+    },
+    types::{
+        alphanumericboth1, datetime_parser, end_of_statement, is_key_char, optional, parenthesized,
+        repeated, schema_map, set, unsigned, uuid_parser, uuid_set,
+    },
 };
 
-pub fn create_content(
-    input: &str,
-) -> IResult<&str, (Option<Vec<String>>, Option<Vec<String>>), VerboseError<&str>> {
+type CreateContent = (
+    bool,
+    Option<Vec<String>>,
+    Option<Vec<String>>,
+    Option<HashMap<String, SchemaType>>,
+);
+type InsertContent = (
+    HashMap<String, Types>,
+    Option<Uuid>,
+    bool,
+    Option<(Vec<String>, ConflictAction)>,
+);
+type UpdateContent = (UpdatePayload, Uuid);
+type MatchUpdateContent = (Clause, UpdatePayload, Uuid);
+type RelationContent = (RelationType, RelationMode);
+type JoinContent<'a> = ((&'a str, &'a str), (&'a str, &'a str));
+type DeleteContent = (Uuid, Option<DateTime<Utc>>);
+type CheckContent = (HashMap<String, Types>, Uuid);
+type SelectContent = (
+    ToSelect,
+    Option<SelectLookup>,
+    Option<When>,
+    Vec<SelectFunction>,
+);
+
+pub fn create_content(input: &str) -> IResult<&str, CreateContent, VerboseError<&str>> {
     preceded(
         sp,
         tuple((
             entity,
+            optional(inner_if_not_exists),
             alt((
                 preceded(sp, alphanumerickey1),
                 delimited(sp, alphanumerickey1, sp),
             )),
+            optional(inner_if_not_exists),
         )),
     )(input)
-    .map(|(next_input, res)| {
-        let options = inner_create_option(next_input);
-        if let Ok((next, (option1, keys1))) = options {
-            let options = inner_create_option(next);
-            if let Ok((_, (_, keys2))) = options {
-                if option1 == CreateOptions::UNIQUES {
-                    (res.1, (Some(keys1), Some(keys2)))
-                } else {
-                    (res.1, (Some(keys2), Some(keys1)))
+    .and_then(|(next_input, res)| {
+        let (mut uniques, mut encrypts, mut schema) = (None, None, None);
+        let duplicate = |at| NomErr::Failure(VerboseError::from_error_kind(at, ErrorKind::Verify));
+        // `IF NOT EXISTS` goes before or after the name, like in INSERT
+        if res.1.is_some() && res.3.is_some() {
+            return Err(duplicate(input));
+        }
+        let mut next = next_input;
+        loop {
+            if let (rest, Some((option, keys))) = optional(inner_create_option)(next)? {
+                let declared = match option {
+                    CreateOptions::UNIQUES => &mut uniques,
+                    CreateOptions::ENCRYPT => &mut encrypts,
+                };
+                if declared.replace(keys).is_some() {
+                    return Err(duplicate(next));
                 }
-            } else if option1 == CreateOptions::UNIQUES {
-                (res.1, (Some(keys1), None))
+                next = rest;
+            } else if let (rest, Some(types)) = optional(inner_create_schema)(next)? {
+                if schema.replace(types).is_some() {
+                    return Err(duplicate(next));
+                }
+                next = rest;
             } else {
-                (res.1, (None, Some(keys1)))
+                break;
             }
-        } else {
-            (res.1, (None, None))
         }
+        end_of_statement(next)?;
+        let if_not_exists = res.1.is_some() || res.3.is_some();
+        Ok((res.2, (if_not_exists, uniques, encrypts, schema)))
     })
 }
 
-pub fn insert_content(
-    input: &str,
-) -> IResult<&str, (HashMap<String, Types>, Option<Uuid>), VerboseError<&str>> {
+pub fn alter_content(input: &str) -> IResult<&str, Vec<Alteration>, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            entity,
+            preceded(sp, alphanumerickey1),
+            terminated(
+                repeated(preceded(
+                    sp,
+                    alt((
+                        map(
+                            preceded(tuple((add, sp)), cut(inner_alter_option)),
+                            |(o, k)| Alteration::Add(o, k),
+                        ),
+                        map(
+                            preceded(tuple((drop, sp)), cut(inner_alter_option)),
+                            |(o, k)| Alteration::Drop(o, k),
+                        ),
+                    )),
+                )),
+                end_of_statement,
+            ),
+        )),
+    )(input)
+    .and_then(|(next, res)| match res.2 {
+        changes if changes.is_empty() => Err(NomErr::Error(VerboseError::from_error_kind(
+            next,
+            ErrorKind::Many1,
+        ))),
+        changes => Ok((res.1, changes)),
+    })
+}
+
+pub fn show_content(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    terminated(preceded(sp1, entities), end_of_statement)(input)
+}
+
+// `DESCRIBE users` or `DESCRIBE ENTITY users`
+pub fn describe_content(input: &str) -> IResult<&str, (), VerboseError<&str>> {
+    preceded(
+        tuple((sp1, optional(terminated(entity, sp1)))),
+        terminated(alphanumerickey1, end_of_statement),
+    )(input)
+    .map(|(_, entity)| (entity, ()))
+}
+
+pub fn drop_content(input: &str) -> IResult<&str, (), VerboseError<&str>> {
+    preceded(
+        tuple((sp1, entity, sp1)),
+        terminated(alphanumerickey1, end_of_statement),
+    )(input)
+    .map(|(_, name)| (name, ()))
+}
+
+pub fn insert_content(input: &str) -> IResult<&str, InsertContent, VerboseError<&str>> {
     preceded(
         sp,
         tuple((
@@ -74,15 +181,36 @@ pub fn insert_content(
             )),
         )),
     )(input)
-    .map(|(next, res)| match inner_insert(next) {
-        Err(_) => (res.2, (res.0, None)),
-        Ok((_, id)) => (res.2, (res.0, Some(id))),
+    .and_then(|(next, res)| {
+        terminated(
+            tuple((
+                optional(inner_insert),
+                optional(inner_if_not_exists),
+                optional(inner_on_conflict),
+            )),
+            end_of_statement,
+        )(next)
+        .map(|(_, (id, if_not_exists, conflict))| {
+            (res.2, (res.0, id, if_not_exists.is_some(), conflict))
+        })
     })
 }
 
-pub fn update_content(
+pub fn insert_many_content(
     input: &str,
-) -> IResult<&str, (&str, HashMap<String, Types>, Uuid), VerboseError<&str>> {
+) -> IResult<&str, Vec<HashMap<String, Types>>, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            hashmap_vector,
+            preceded(sp, into),
+            terminated(preceded(sp, alphanumerickey1), end_of_statement),
+        )),
+    )(input)
+    .map(|(_, res)| (res.2, res.0))
+}
+
+pub fn update_content(input: &str) -> IResult<&str, UpdateContent, VerboseError<&str>> {
     preceded(
         sp,
         tuple((
@@ -90,34 +218,141 @@ pub fn update_content(
                 preceded(sp, alphanumerickey1),
                 delimited(sp, alphanumerickey1, sp),
             )),
-            alt((preceded(sp, keyword_set), preceded(sp, content))),
-            preceded(sp, hashmap),
+            alt((
+                map(
+                    preceded(preceded(sp, keyword_set), preceded(sp, path_hashmap)),
+                    UpdatePayload::Set,
+                ),
+                map(
+                    preceded(preceded(sp, content), preceded(sp, content_op_map)),
+                    UpdatePayload::Content,
+                ),
+            )),
             preceded(sp, into),
-            preceded(sp, uuid_parser),
+            terminated(preceded(sp, uuid_parser), end_of_statement),
         )),
     )(input)
-    .map(|(_, res)| (res.0, (res.1, res.2, res.4)))
+    .map(|(_, res)| (res.0, (res.1, res.3)))
 }
 
-pub fn evict_content(input: &str) -> IResult<&str, Option<&str>, VerboseError<&str>> {
-    preceded(sp, tuple((preceded(sp, alphanumericboth1),)))(input).map(|(next, res)| {
-        match tuple((preceded(sp, from), preceded(sp, alphanumerickey1)))(next) {
-            Ok(inner) => (res.0, Some(inner.1 .1)),
-            Err(_) => (res.0, None),
-        }
+pub fn match_update_content(input: &str) -> IResult<&str, MatchUpdateContent, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            alt((
+                map(clause_group("all"), Clause::And),
+                map(clause_group("any"), Clause::Or),
+            )),
+            preceded(sp, update),
+        )),
+    )(input)
+    .and_then(|(next, (conditions, _))| {
+        update_content(next).map(|(entity, (payload, id))| (entity, (conditions, payload, id)))
     })
 }
 
-pub fn delete_content(input: &str) -> IResult<&str, Uuid, VerboseError<&str>> {
+pub fn relation_content(input: &str) -> IResult<&str, RelationContent, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            relation_type,
+            preceded(sp, relation_mode),
+            terminated(preceded(sp, parenthesized), end_of_statement),
+        )),
+    )(input)
+    .map(|(_, res)| (res.2, (res.0, res.1)))
+}
+
+pub fn join_content(input: &str) -> IResult<&str, JoinContent<'_>, VerboseError<&str>> {
+    preceded(
+        sp,
+        delimited(
+            char('('),
+            tuple((
+                preceded(sp, join_key),
+                preceded(tuple((sp, char(','), sp)), join_key),
+            )),
+            preceded(sp, char(')')),
+        ),
+    )(input)
+}
+
+pub fn evict_content(input: &str) -> IResult<&str, Option<&str>, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            preceded(sp, alphanumericboth1),
+            terminated(
+                optional(preceded(tuple((sp, from, sp)), alphanumerickey1)),
+                end_of_statement,
+            ),
+        )),
+    )(input)
+    .map(|(_, res)| res)
+}
+
+pub fn evict_keys_content(input: &str) -> IResult<&str, (Vec<String>, Uuid), VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            verify(set, |keys: &Vec<String>| !keys.is_empty()),
+            preceded(sp, from),
+            preceded(sp, alphanumerickey1),
+            terminated(preceded(tuple((sp, id, sp)), uuid_parser), end_of_statement),
+        )),
+    )(input)
+    .map(|(_, res)| (res.2, (res.0, res.3)))
+}
+
+pub fn delete_content(input: &str) -> IResult<&str, DeleteContent, VerboseError<&str>> {
     preceded(
         sp,
         tuple((
             preceded(sp, uuid_parser),
             preceded(sp, from),
             preceded(sp, alphanumerickey1),
+            terminated(
+                optional(preceded(tuple((sp, at, sp)), cut(datetime_parser))),
+                end_of_statement,
+            ),
         )),
     )(input)
-    .map(|(_, res)| (res.2, res.0))
+    .map(|(_, res)| (res.2, (res.0, res.3)))
+}
+
+pub fn check_content(input: &str) -> IResult<&str, CheckContent, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            hashmap,
+            preceded(sp, from),
+            preceded(sp, alphanumerickey1),
+            terminated(preceded(tuple((sp, id, sp)), uuid_parser), end_of_statement),
+        )),
+    )(input)
+    .map(|(_, res)| (res.2, (res.0, res.3)))
+}
+
+pub fn select_content(input: &str) -> IResult<&str, SelectContent, VerboseError<&str>> {
+    preceded(
+        sp,
+        tuple((
+            alt((map(char('*'), |_| ToSelect::All), map(set, ToSelect::Keys))),
+            preceded(sp, from),
+            preceded(sp, alphanumerickey1),
+        )),
+    )(input)
+    .and_then(|(next, res)| {
+        terminated(
+            tuple((
+                optional(inner_select_lookup),
+                optional(inner_select_when),
+                repeated(inner_select_function),
+            )),
+            end_of_statement,
+        )(next)
+        .map(|(_, (lookup, when, functions))| (res.2, (res.0, lookup, when, functions)))
+    })
 }
 
 fn inner_create_option(
@@ -126,10 +361,144 @@ fn inner_create_option(
     preceded(sp, tuple((create_options, sp, set)))(input).map(|(next, v)| (next, (v.0, v.2)))
 }
 
+// An alteration that changes no keys is most likely a mistake
+fn inner_alter_option(
+    input: &str,
+) -> IResult<&str, (CreateOptions, Vec<String>), VerboseError<&str>> {
+    verify(inner_create_option, |(_, keys)| !keys.is_empty())(input)
+}
+
+fn inner_select_lookup(input: &str) -> IResult<&str, SelectLookup, VerboseError<&str>> {
+    preceded(
+        sp,
+        alt((
+            map(
+                preceded(tuple((ids, sp, in_keyword, sp)), cut(uuid_set)),
+                SelectLookup::Ids,
+            ),
+            map(
+                preceded(tuple((id, sp)), cut(uuid_parser)),
+                SelectLookup::Id,
+            ),
+            map(
+                preceded(tuple((where_keyword, sp)), cut(clauses)),
+                SelectLookup::Where,
+            ),
+        )),
+    )(input)
+}
+
+fn inner_select_when(input: &str) -> IResult<&str, When, VerboseError<&str>> {
+    preceded(
+        tuple((sp, when, sp)),
+        cut(alt((
+            map(preceded(tuple((at, sp)), datetime_parser), When::At),
+            map(
+                verify(
+                    tuple((
+                        preceded(tuple((start, sp)), datetime_parser),
+                        preceded(tuple((sp, end, sp)), datetime_parser),
+                    )),
+                    |(start, end)| start <= end,
+                ),
+                |(start, end)| When::Range(start, end),
+            ),
+        ))),
+    )(input)
+}
+
+fn inner_select_function(input: &str) -> IResult<&str, SelectFunction, VerboseError<&str>> {
+    preceded(
+        sp,
+        alt((
+            map(
+                preceded(
+                    tuple((order, sp, by, sp)),
+                    cut(tuple((
+                        alphanumerickey1,
+                        map(optional(preceded(sp, order_direction)), |direction| {
+                            direction.unwrap_or(Order::Asc)
+                        }),
+                    ))),
+                ),
+                |(key, direction)| SelectFunction::OrderBy(key.to_string(), direction),
+            ),
+            map(
+                preceded(tuple((group, sp, by, sp)), cut(alphanumerickey1)),
+                |key| SelectFunction::GroupBy(key.to_string()),
+            ),
+            map(
+                preceded(tuple((limit, sp)), cut(unsigned)),
+                SelectFunction::Limit,
+            ),
+            map(
+                preceded(tuple((offset, sp)), cut(unsigned)),
+                SelectFunction::Offset,
+            ),
+            map(preceded(tuple((dedup, sp)), cut(alphanumerickey1)), |key| {
+                SelectFunction::Dedup(key.to_string())
+            }),
+            map(terminated(count, not(satisfy(is_key_char))), |_| {
+                SelectFunction::Count
+            }),
+        )),
+    )(input)
+}
+
+// Like `sp`, but something must be skipped, so that keywords don't run together
+fn sp1(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    verify(sp, |skipped: &str| !skipped.is_empty())(input)
+}
+
+fn join_key(input: &str) -> IResult<&str, (&str, &str), VerboseError<&str>> {
+    separated_pair(alphanumerickey1, char(':'), alphanumerickey1)(input)
+}
+
+fn inner_create_schema(
+    input: &str,
+) -> IResult<&str, HashMap<String, SchemaType>, VerboseError<&str>> {
+    preceded(tuple((sp, schema, sp)), cut(schema_map))(input)
+}
+
 fn inner_insert(input: &str) -> IResult<&str, Uuid, VerboseError<&str>> {
     preceded(sp, tuple((with, sp, uuid_parser)))(input).map(|(next, v)| (next, v.2))
 }
 
+fn inner_if_not_exists(input: &str) -> IResult<&str, (), VerboseError<&str>> {
+    map(tuple((sp, if_keyword, sp, not_keyword, sp, exists)), |_| ())(input)
+}
+
+fn inner_on_conflict(
+    input: &str,
+) -> IResult<&str, (Vec<String>, ConflictAction), VerboseError<&str>> {
+    preceded(
+        tuple((sp, on, sp, conflict)),
+        cut(tuple((
+            preceded(
+                tuple((sp, char('('))),
+                terminated(
+                    separated_list1(
+                        tuple((sp, char(','), sp)),
+                        map(preceded(sp, alphanumerickey1), String::from),
+                    ),
+                    tuple((sp, char(')'))),
+                ),
+            ),
+            preceded(
+                sp,
+                alt((
+                    map(tuple((update, sp, keyword_set)), |_| {
+                        ConflictAction::UpdateSet
+                    }),
+                    map(tuple((do_keyword, sp, nothing)), |_| {
+                        ConflictAction::DoNothing
+                    }),
+                )),
+            ),
+        ))),
+    )(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,11 +506,11 @@ mod tests {
     #[test]
     fn create_content_empty() {
         assert_eq!(
-            Ok(("hello_world", (None, None))),
+            Ok(("hello_world", (false, None, None, None))),
             create_content("ENTITY hello_world")
         );
         assert_eq!(
-            Ok(("hello_world", (None, None))),
+            Ok(("hello_world", (false, None, None, None))),
             create_content("ENTITY hello_world ")
         );
     }
@@ -151,7 +520,12 @@ mod tests {
         assert_eq!(
             Ok((
                 "hello_world",
-                (Some(vec!["hello".to_string(), "world".to_string()]), None)
+                (
+                    false,
+                    Some(vec!["hello".to_string(), "world".to_string()]),
+                    None,
+                    None
+                )
             )),
             create_content("ENTITY hello_world UNIQUES #{hello, world}")
         );
@@ -162,7 +536,12 @@ mod tests {
         assert_eq!(
             Ok((
                 "hello_world",
-                (None, Some(vec!["hello".to_string(), "world".to_string()]))
+                (
+                    false,
+                    None,
+                    Some(vec!["hello".to_string(), "world".to_string()]),
+                    None
+                )
             )),
             create_content("ENTITY hello_world Encrypt #{hello, world}")
         );
@@ -174,11 +553,275 @@ mod tests {
             Ok((
                 "hello_world",
                 (
+                    false,
                     Some(vec!["hello".to_string(), "world".to_string()]),
-                    Some(vec!["hello2".to_string(), "world2".to_string()])
+                    Some(vec!["hello2".to_string(), "world2".to_string()]),
+                    None
                 )
             )),
             create_content("ENTITY hello_world UNIQUES #{hello, world} Encrypt #{hello2, world2}")
         );
     }
+
+    #[test]
+    fn create_content_schema() {
+        assert_eq!(
+            Ok((
+                "users",
+                (
+                    true,
+                    None,
+                    Some(vec!["ssn".to_string()]),
+                    Some(
+                        [
+                            ("name".to_string(), SchemaType::String),
+                            ("age".to_string(), SchemaType::Integer),
+                        ]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, SchemaType>>()
+                    )
+                )
+            )),
+            create_content(
+                "ENTITY IF NOT EXISTS users SCHEMA {name: String, age: Integer} ENCRYPT #{ssn}"
+            )
+        );
+        assert!(create_content("ENTITY users SCHEMA {age: Number}").is_err());
+        assert_eq!(
+            Ok(("users", (true, Some(vec!["email".to_string()]), None, None))),
+            create_content("ENTITY users IF NOT EXISTS UNIQUES #{email}")
+        );
+        assert!(create_content("ENTITY IF NOT EXISTS users IF NOT EXISTS").is_err());
+    }
+
+    #[test]
+    fn create_content_duplicate_options() {
+        assert!(create_content("ENTITY users UNIQUES #{a} UNIQUES #{b}").is_err());
+        assert!(create_content("ENTITY users ENCRYPT #{a} UNIQUES #{b} ENCRYPT #{c}").is_err());
+        assert!(create_content("ENTITY users SCHEMA {a: Integer} SCHEMA {b: String}").is_err());
+    }
+
+    #[test]
+    fn create_content_unknown_option() {
+        assert!(create_content("ENTITY users ENCRPYT #{ssn}").is_err());
+        assert!(create_content("ENTITY users UNIQUES #{a} GARBAGE #{b}").is_err());
+        assert!(create_content("ENTITY users garbage").is_err());
+    }
+
+    #[test]
+    fn check_content_test() {
+        assert_eq!(
+            Ok((
+                "users",
+                (
+                    [("password".to_string(), Types::String("s3cret".to_string()))]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, Types>>(),
+                    Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()
+                )
+            )),
+            check_content(
+                " {password: \"s3cret\"} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00"
+            )
+        );
+        assert!(check_content(
+            " {password: \"s3cret\"} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00 garbage"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn join_content_test() {
+        assert_eq!(
+            Ok((
+                " SELECT * FROM users | SELECT * FROM orders",
+                (("users", "id"), ("orders", "user_id"))
+            )),
+            join_content(" (users:id, orders:user_id) SELECT * FROM users | SELECT * FROM orders")
+        );
+    }
+
+    #[test]
+    fn delete_content_at() {
+        assert_eq!(
+            Ok((
+                "my_entity",
+                (
+                    Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                    Some("2014-11-28T12:00:09Z".parse().unwrap())
+                )
+            )),
+            delete_content(
+                " 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM my_entity AT 2014-11-28T12:00:09Z"
+            )
+        );
+        assert!(
+            delete_content(" 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM my_entity AT noon").is_err()
+        );
+    }
+
+    #[test]
+    fn insert_content_on_conflict() {
+        assert_eq!(
+            Ok((
+                "users",
+                (
+                    [("email".to_string(), Types::String("a@b.c".to_string()))]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, Types>>(),
+                    None,
+                    false,
+                    Some((
+                        vec!["email".to_string(), "name".to_string()],
+                        ConflictAction::DoNothing
+                    ))
+                )
+            )),
+            insert_content(" {email: \"a@b.c\"} INTO users ON CONFLICT (email, name) DO NOTHING")
+        );
+        assert!(
+            insert_content(" {email: \"a@b.c\"} INTO users ON CONFLICT (email) IGNORE").is_err()
+        );
+    }
+
+    #[test]
+    fn alter_content_test() {
+        assert_eq!(
+            Ok((
+                "users",
+                vec![
+                    Alteration::Add(CreateOptions::UNIQUES, vec!["email".to_string()]),
+                    Alteration::Drop(
+                        CreateOptions::ENCRYPT,
+                        vec!["ssn".to_string(), "pin".to_string()]
+                    ),
+                ]
+            )),
+            alter_content(" ENTITY users ADD UNIQUES #{email} DROP ENCRYPT #{ssn, pin}")
+        );
+        assert!(alter_content(" ENTITY users").is_err());
+        assert!(alter_content(" ENTITY users ADD PRIMARY #{email}").is_err());
+        assert!(alter_content(" ENTITY users ADD UNIQUES #{a} REMOVE ENCRYPT #{b}").is_err());
+        assert!(alter_content(" ENTITY users ADD UNIQUES #{}").is_err());
+    }
+
+    #[test]
+    fn select_content_all() {
+        assert_eq!(
+            Ok(("my_entity", (ToSelect::All, None, None, vec![]))),
+            select_content(" * FROM my_entity")
+        );
+    }
+
+    #[test]
+    fn select_content_keys() {
+        assert_eq!(
+            Ok((
+                "my_entity",
+                (
+                    ToSelect::Keys(vec!["name".to_string(), "age".to_string()]),
+                    None,
+                    None,
+                    vec![]
+                )
+            )),
+            select_content(" #{name, age} FROM my_entity")
+        );
+    }
+
+    #[test]
+    fn select_content_ids() {
+        assert_eq!(
+            Ok((
+                "my_entity",
+                (
+                    ToSelect::All,
+                    Some(SelectLookup::Ids(vec![
+                        Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                        Uuid::parse_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap(),
+                    ])),
+                    None,
+                    vec![]
+                )
+            )),
+            select_content(
+                " * FROM my_entity IDS IN #{2e796540-ee72-40fd-b4a2-a2315d697d00, 634f6c5b-476f-4cc0-97d0-c1c9468cf8d8}"
+            )
+        );
+    }
+
+    #[test]
+    fn select_content_invalid_id() {
+        assert!(select_content(" * FROM my_entity ID not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn select_content_when_range() {
+        assert_eq!(
+            Ok((
+                "my_entity",
+                (
+                    ToSelect::All,
+                    Some(SelectLookup::Id(
+                        Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()
+                    )),
+                    Some(When::Range(
+                        "2014-11-28T12:00:09Z".parse().unwrap(),
+                        "2014-11-28T21:00:09+09:00".parse().unwrap()
+                    )),
+                    vec![]
+                )
+            )),
+            select_content(
+                " * FROM my_entity ID 2e796540-ee72-40fd-b4a2-a2315d697d00 WHEN START 2014-11-28T12:00:09Z END 2014-11-28T21:00:09+09:00"
+            )
+        );
+    }
+
+    #[test]
+    fn select_content_when_range_reversed() {
+        assert!(select_content(
+            " * FROM my_entity WHEN START 2014-11-28T12:00:09Z END 2013-11-28T12:00:09Z"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn select_content_functions() {
+        assert_eq!(
+            Ok((
+                "my_entity",
+                (
+                    ToSelect::All,
+                    None,
+                    None,
+                    vec![
+                        SelectFunction::GroupBy("city".to_string()),
+                        SelectFunction::OrderBy("age".to_string(), Order::Desc),
+                        SelectFunction::OrderBy("name".to_string(), Order::Asc),
+                        SelectFunction::Dedup("name".to_string()),
+                        SelectFunction::Offset(20),
+                        SelectFunction::Limit(10),
+                        SelectFunction::Count,
+                    ]
+                )
+            )),
+            select_content(
+                " * FROM my_entity GROUP BY city ORDER BY age :desc ORDER BY name DEDUP name OFFSET 20 LIMIT 10 COUNT"
+            )
+        );
+    }
+
+    #[test]
+    fn select_content_invalid_limit() {
+        assert!(select_content(" * FROM my_entity LIMIT ten").is_err());
+    }
+
+    #[test]
+    fn select_content_invalid_when() {
+        assert!(select_content(" * FROM my_entity WHEN AT yesterday").is_err());
+    }
 }
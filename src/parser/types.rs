@@ -1,23 +1,25 @@
-use std::{collections::HashMap, num::ParseIntError};
+use std::{collections::HashMap, convert::TryFrom, num::ParseIntError};
 
 use chrono::{DateTime, Utc};
 use nom::{
     branch::alt,
-    bytes::streaming::{escaped, tag, tag_no_case, take_while},
+    bytes::streaming::{escaped, tag, tag_no_case},
     character::{
-        complete::{anychar, char},
+        complete::{anychar, char, digit1},
         is_alphanumeric, is_digit, is_newline, is_space,
         streaming::one_of,
     },
-    combinator::{cut, map, map_res, recognize, value},
+    combinator::{cut, map, map_res, opt, peek, recognize, value},
     error::{context, ErrorKind, ParseError, VerboseError},
-    multi::separated_list0,
-    sequence::{preceded, separated_pair, terminated},
-    AsChar, IResult, InputTakeAtPosition,
+    multi::{many0, separated_list0, separated_list1},
+    number::streaming::double,
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    AsChar, Err as NomErr, IResult, InputTakeAtPosition, Needed,
 };
 use uuid::Uuid;
 
-use crate::model::types::{wql_value, Nil, Types};
+use crate::logic::closing_bracket;
+use crate::model::types::{wql_value, ContentOp, Nil, Placeholder, SchemaType, Types};
 
 pub fn uuid_parser(s: &str) -> IResult<&str, Uuid, VerboseError<&str>> {
     map_res(recognize(alphanumerichyphen), Uuid::parse_str)(s)
@@ -33,6 +35,10 @@ pub fn integer(num: &str) -> IResult<&str, i128, VerboseError<&str>> {
         .map(|(next, res)| (next, i128_parser(&res[..res.len() - 1]).unwrap_or(0)))
 }
 
+pub fn unsigned(num: &str) -> IResult<&str, usize, VerboseError<&str>> {
+    context("unsigned", map_res(digit1, |n: &str| n.parse::<usize>()))(num)
+}
+
 pub fn precise_number_parser(num: &str) -> IResult<&str, String, VerboseError<&str>> {
     context(
         "precise_number",
@@ -83,14 +89,140 @@ pub fn set(input: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     })
 }
 
+pub fn uuid_set(input: &str) -> IResult<&str, Vec<Uuid>, VerboseError<&str>> {
+    context(
+        "uuid set",
+        preceded(
+            tag("#{"),
+            cut(terminated(
+                separated_list0(preceded(char(','), sp), preceded(sp, uuid_parser)),
+                alt((tag("}"), tag(",}"), preceded(sp, tag("}")))),
+            )),
+        ),
+    )(input)
+}
+
+pub fn parenthesized(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    match closing_bracket(input) {
+        Some(i) if input.starts_with('(') => Ok((&input[i + 1..], &input[1..i])),
+        _ => Err(NomErr::Error(VerboseError::from_error_kind(
+            input,
+            ErrorKind::Char,
+        ))),
+    }
+}
+
 pub fn hashmap(input: &str) -> IResult<&str, HashMap<String, Types>, VerboseError<&str>> {
+    context("map", map_of(key_value))(input)
+}
+
+// `UPDATE ... CONTENT` map, values may carry an operator like `+1i`, `append [..]` or `remove`.
+// A signed number is added, so `-2i` subtracts 2 and `set -2i` stores -2
+pub fn content_op_map(
+    input: &str,
+) -> IResult<&str, HashMap<String, ContentOp>, VerboseError<&str>> {
+    context("content map", map_of(content_op_key_value))(input)
+}
+
+fn content_op_key_value(input: &str) -> IResult<&str, (&str, ContentOp), VerboseError<&str>> {
+    separated_pair(
+        preceded(sp, path_key),
+        cut(preceded(sp, char(':'))),
+        content_op,
+    )(input)
+}
+
+pub fn content_op(input: &str) -> IResult<&str, ContentOp, VerboseError<&str>> {
+    preceded(
+        sp,
+        alt((
+            map(preceded(char('+'), cut(addend)), ContentOp::Add),
+            map(preceded(peek(char('-')), addend), ContentOp::Add),
+            map(
+                preceded(terminated(tag_no_case("append"), sp), wql_value),
+                ContentOp::Append,
+            ),
+            value(ContentOp::Remove, tag_no_case("remove")),
+            map(
+                preceded(terminated(tag_no_case("set"), sp), wql_value),
+                ContentOp::Set,
+            ),
+            map(wql_value, ContentOp::Set),
+        )),
+    )(input)
+}
+
+// Only numbers can be added
+fn addend(input: &str) -> IResult<&str, Types, VerboseError<&str>> {
     context(
-        "map",
+        "number",
+        alt((
+            map(placeholder, Types::Placeholder),
+            map(precise_number_parser, Types::Precise),
+            map(integer, Types::Integer),
+            map(double, Types::Float),
+        )),
+    )(input)
+}
+
+// Same as `hashmap`, but keys can be paths like `address.city` or `tags[0]`
+pub fn path_hashmap(input: &str) -> IResult<&str, HashMap<String, Types>, VerboseError<&str>> {
+    context("map", map_of(path_key_value))(input)
+}
+
+fn map_of<'a, F, V>(
+    entry: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, HashMap<String, V>, VerboseError<&'a str>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, (&'a str, V), VerboseError<&'a str>>,
+{
+    preceded(
+        char('{'),
+        cut(terminated(
+            map(
+                separated_list0(preceded(sp, char(',')), entry),
+                |tuple_vec| {
+                    tuple_vec
+                        .into_iter()
+                        .map(|(k, v)| (String::from(k), v))
+                        .collect()
+                },
+            ),
+            alt((tag("}"), tag(",}"), preceded(sp, tag("}")))),
+        )),
+    )
+}
+
+pub fn hashmap_vector(
+    input: &str,
+) -> IResult<&str, Vec<HashMap<String, Types>>, VerboseError<&str>> {
+    context(
+        "map vector",
+        preceded(
+            tag("["),
+            cut(terminated(
+                separated_list1(preceded(sp, char(',')), preceded(sp, hashmap)),
+                preceded(tuple((sp, opt(char(',')), sp)), tag("]")),
+            )),
+        ),
+    )(input)
+}
+
+pub fn schema_map(input: &str) -> IResult<&str, HashMap<String, SchemaType>, VerboseError<&str>> {
+    context(
+        "schema",
         preceded(
             char('{'),
             cut(terminated(
                 map(
-                    separated_list0(preceded(sp, char(',')), key_value),
+                    separated_list0(
+                        preceded(sp, char(',')),
+                        separated_pair(
+                            preceded(sp, alphanumerickey),
+                            cut(preceded(sp, char(':'))),
+                            preceded(sp, map_res(alphanumerickey, SchemaType::try_from)),
+                        ),
+                    ),
                     |tuple_vec| {
                         tuple_vec
                             .into_iter()
@@ -112,6 +244,27 @@ fn key_value(input: &str) -> IResult<&str, (&str, Types), VerboseError<&str>> {
     )(input)
 }
 
+fn path_key_value(input: &str) -> IResult<&str, (&str, Types), VerboseError<&str>> {
+    separated_pair(
+        preceded(sp, path_key),
+        cut(preceded(sp, char(':'))),
+        wql_value,
+    )(input)
+}
+
+pub fn path_key(input: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    context(
+        "path key",
+        recognize(tuple((
+            alphanumerickey1,
+            many0(alt((
+                recognize(preceded(char('.'), alphanumerickey1)),
+                recognize(delimited(char('['), digit1, char(']'))),
+            ))),
+        ))),
+    )(input)
+}
+
 pub fn vector(input: &str) -> IResult<&str, Vec<Types>, VerboseError<&str>> {
     context(
         "vector",
@@ -147,6 +300,21 @@ pub fn boolean(input: &str) -> IResult<&str, bool, VerboseError<&str>> {
     alt((parse_true, parse_false))(input)
 }
 
+pub fn placeholder(input: &str) -> IResult<&str, Placeholder, VerboseError<&str>> {
+    context(
+        "placeholder",
+        alt((
+            map(preceded(char('$'), unsigned), Placeholder::Index),
+            map(preceded(char('$'), alphanumerickey1), |name: &str| {
+                Placeholder::Variable(name.to_string())
+            }),
+            map(preceded(char(':'), alphanumerickey1), |name: &str| {
+                Placeholder::Name(name.to_string())
+            }),
+        )),
+    )(input)
+}
+
 pub fn nil(input: &str) -> IResult<&str, Nil, VerboseError<&str>> {
     let mut parse_nil = value(Nil, tag_no_case("nil"));
 
@@ -269,11 +437,94 @@ where
     )
 }
 
-// Any space char
+// Like `opt`, but also recovers from `Incomplete` and only propagates `Failure`
+pub(crate) fn optional<'a, O, F>(
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Option<O>, VerboseError<&'a str>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O, VerboseError<&'a str>>,
+{
+    move |input: &'a str| match parser(input) {
+        Ok((next, res)) => Ok((next, Some(res))),
+        Err(NomErr::Failure(e)) => Err(NomErr::Failure(e)),
+        Err(_) => Ok((input, None)),
+    }
+}
+
+// Like `many0`, but with `optional` semantics on each repetition
+pub(crate) fn repeated<'a, O, F>(
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>, VerboseError<&'a str>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O, VerboseError<&'a str>>,
+{
+    move |input: &'a str| {
+        let mut results = Vec::new();
+        let mut next = input;
+        while let (rest, Some(res)) = optional(&mut parser)(next)? {
+            results.push(res);
+            next = rest;
+        }
+        Ok((next, results))
+    }
+}
+
+// Whitespace, `-- line` and `/* block */` comments.
+// As a streaming parser, running out of input is `Incomplete`,
+// but an unterminated block comment is a failure
 pub(crate) fn sp<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     let chars = " \t\r\n";
 
-    take_while(move |c| chars.contains(c))(i)
+    let mut rest = i;
+    loop {
+        rest = rest.trim_start_matches(|c| chars.contains(c));
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = match comment.find("*/") {
+                Some(end) => &comment[end + 2..],
+                None => {
+                    return Err(NomErr::Failure(E::from_error_kind(
+                        rest,
+                        ErrorKind::TakeUntil,
+                    )))
+                }
+            };
+        } else {
+            break;
+        }
+    }
+    match rest {
+        "" => Err(NomErr::Incomplete(Needed::new(1))),
+        _ => Ok((rest, &i[..i.len() - rest.len()])),
+    }
+}
+
+// Only whitespace, comments or a `;` may follow a statement
+pub(crate) fn end_of_statement(input: &str) -> IResult<&str, (), VerboseError<&str>> {
+    let rest = match sp::<VerboseError<&str>>(input) {
+        Ok((rest, _)) => rest,
+        Err(NomErr::Incomplete(_)) => "",
+        Err(e) => return Err(e),
+    };
+    if rest.is_empty() || rest.starts_with(';') {
+        Ok((rest, ()))
+    } else {
+        Err(NomErr::Error(VerboseError::from_error_kind(
+            rest,
+            ErrorKind::Eof,
+        )))
+    }
+}
+
+// Characters that can continue a key or keyword, `not(satisfy(is_key_char))` ends a word
+pub(crate) fn is_key_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+// Nothing but whitespace and comments
+pub(crate) fn is_blank(i: &str) -> bool {
+    matches!(sp::<VerboseError<&str>>(i), Err(NomErr::Incomplete(_)))
 }
 
 #[cfg(test)]
@@ -329,6 +580,31 @@ mod test {
         );
     }
 
+    #[test]
+    fn uuid_set_test() {
+        assert_eq!(
+            uuid_set(
+                "#{c15a23cd-22d8-4351-b738-396b274599f8, 634f6c5b-476f-4cc0-97d0-c1c9468cf8d8} WTF"
+            ),
+            Ok((
+                " WTF",
+                vec![
+                    Uuid::from_str("c15a23cd-22d8-4351-b738-396b274599f8").unwrap(),
+                    Uuid::from_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap()
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn parenthesized_test() {
+        assert_eq!(
+            parenthesized("(SELECT * FROM a, SELECT #{b} FROM b) WTF"),
+            Ok((" WTF", "SELECT * FROM a, SELECT #{b} FROM b"))
+        );
+        assert!(parenthesized("(SELECT * FROM a").is_err());
+    }
+
     #[test]
     fn test_string() {
         assert_eq!(
@@ -396,7 +672,7 @@ mod test {
         assert_eq!(
             Ok((
                 "",
-                vec![(
+                [(
                     "a".to_owned(),
                     Types::Uuid(Uuid::from_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap())
                 )]
@@ -413,7 +689,7 @@ mod test {
         assert_eq!(
             Ok((
                 "",
-                vec![(
+                [(
                     "a".to_owned(),
                     Types::Uuid(Uuid::from_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap())
                 )]
@@ -430,7 +706,7 @@ mod test {
         assert_eq!(
             Ok((
                 "",
-                vec![(
+                [(
                     "a".to_owned(),
                     Types::Uuid(Uuid::from_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap())
                 )]
@@ -442,6 +718,146 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_map_vector() {
+        assert_eq!(
+            Ok((
+                "",
+                vec![
+                    [("a".to_owned(), Types::Integer(1))]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, Types>>(),
+                    [("b".to_owned(), Types::Boolean(true))]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, Types>>(),
+                ]
+            )),
+            hashmap_vector("[{a: 1i}, {b: true} ,]")
+        );
+        assert!(hashmap_vector("[]").is_err());
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(placeholder("$1,"), Ok((",", Placeholder::Index(1))));
+        assert_eq!(
+            placeholder(":user_name}"),
+            Ok(("}", Placeholder::Name("user_name".to_string())))
+        );
+        assert_eq!(
+            wql_value(" :name,"),
+            Ok((
+                ",",
+                Types::Placeholder(Placeholder::Name("name".to_string()))
+            ))
+        );
+        assert_eq!(
+            placeholder("$user}"),
+            Ok(("}", Placeholder::Variable("user".to_string())))
+        );
+        assert!(placeholder("$").is_err());
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            sp::<VerboseError<&str>>("  -- note\n /* multi\n line */ {a: 1i}"),
+            Ok(("{a: 1i}", "  -- note\n /* multi\n line */ "))
+        );
+        assert_eq!(
+            hashmap("{a: \"-- not /* a */ comment\", /* skip */ b: 2i -- end\n}"),
+            Ok((
+                "",
+                [
+                    (
+                        "a".to_owned(),
+                        Types::String("-- not /* a */ comment".to_owned())
+                    ),
+                    ("b".to_owned(), Types::Integer(2))
+                ]
+                .iter()
+                .cloned()
+                .collect::<HashMap<String, Types>>()
+            ))
+        );
+        assert!(is_blank(" -- only a comment\n/* and */ "));
+        assert!(!is_blank(" /* unterminated "));
+        assert!(matches!(
+            sp::<VerboseError<&str>>(" /* unterminated {a: 1i}"),
+            Err(NomErr::Failure(_))
+        ));
+        assert!(!is_blank(" -- comment\n COMMIT"));
+    }
+
+    #[test]
+    fn path_keys() {
+        assert_eq!(path_key("address.city: 1"), Ok((": 1", "address.city")));
+        assert_eq!(path_key("tags[0].name }"), Ok((" }", "tags[0].name")));
+        assert_eq!(path_key("tags[a]"), Ok(("[a]", "tags")));
+        assert_eq!(
+            Ok((
+                "",
+                [
+                    ("address.city".to_owned(), Types::String("Seoul".to_owned())),
+                    ("tags[0]".to_owned(), Types::String("vip".to_owned()))
+                ]
+                .iter()
+                .cloned()
+                .collect::<HashMap<String, Types>>()
+            )),
+            path_hashmap("{address.city: \"Seoul\", tags[0]: \"vip\"}")
+        );
+        assert!(hashmap("{address.city: \"Seoul\"}").is_err());
+    }
+
+    #[test]
+    fn content_ops() {
+        assert_eq!(
+            Ok((
+                "",
+                [
+                    ("count".to_owned(), ContentOp::Add(Types::Integer(1))),
+                    (
+                        "tags".to_owned(),
+                        ContentOp::Append(Types::Vector(vec![Types::String("x".to_owned())]))
+                    ),
+                    ("cash".to_owned(), ContentOp::Add(Types::Float(-10.5))),
+                    ("old".to_owned(), ContentOp::Remove),
+                    (
+                        "name".to_owned(),
+                        ContentOp::Set(Types::String("Julia".to_owned()))
+                    ),
+                ]
+                .iter()
+                .cloned()
+                .collect::<HashMap<String, ContentOp>>()
+            )),
+            content_op_map(
+                "{count: +1i, tags: append [\"x\"], cash: -10.5, old: remove, name: \"Julia\"}"
+            )
+        );
+        assert!(content_op_map("{a: +\"x\"}").is_err());
+        assert!(content_op_map("{a: +[1i]}").is_err());
+        assert!(content_op_map("{a: +{b: 1i}}").is_err());
+        assert_eq!(
+            content_op(" set -2i}"),
+            Ok(("}", ContentOp::Set(Types::Integer(-2))))
+        );
+        assert_eq!(
+            content_op(" -2i}"),
+            Ok(("}", ContentOp::Add(Types::Integer(-2))))
+        );
+        assert_eq!(
+            content_op(" +$1}"),
+            Ok((
+                "}",
+                ContentOp::Add(Types::Placeholder(Placeholder::Index(1)))
+            ))
+        );
+    }
+
     #[test]
     fn test_char_parse() {
         assert_eq!(char_parse("'h'"), Ok(("", 'h')));
@@ -466,7 +882,7 @@ mod test {
         assert_eq!(
             Ok((
                 "",
-                vec![
+                [
                     (
                         "a".to_owned(),
                         Types::Uuid(
@@ -502,7 +918,6 @@ mod test {
     }
 
     fn datetime() -> DateTime<Utc> {
-        let dt = Utc.ymd(2014, 11, 28).and_hms(12, 0, 9);
-        dt
+        Utc.with_ymd_and_hms(2014, 11, 28, 12, 0, 9).unwrap()
     }
 }
@@ -1,9 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, str::FromStr};
+    use std::{
+        collections::{BTreeSet, HashMap},
+        str::FromStr,
+    };
 
     use uuid::Uuid;
-    use wql_nom::{parse_wql, Types, Wql};
+    use wql_nom::{
+        parse_wql, parse_wql_script, prepare_wql, Alteration, Clause, Comparison, ConflictAction,
+        ContentOp, CreateOptions, Explanation, Order, Placeholder, RelationMode, RelationType,
+        SchemaType, SelectFunction, ToSelect, Types, Wql, WqlError,
+    };
 
     #[test]
     fn create_content_empty() {
@@ -11,7 +18,9 @@ mod tests {
             Ok(Wql::CreateEntity {
                 name: "hello_world".to_owned(),
                 uniques: None,
-                encrypts: None
+                encrypts: None,
+                schema: None,
+                if_not_exists: false
             }),
             parse_wql("create ENTITY hello_world")
         );
@@ -19,7 +28,9 @@ mod tests {
             Ok(Wql::CreateEntity {
                 name: "hello_world".to_owned(),
                 uniques: None,
-                encrypts: None
+                encrypts: None,
+                schema: None,
+                if_not_exists: false
             }),
             parse_wql("create ENTITY hello_world ")
         );
@@ -31,7 +42,9 @@ mod tests {
             Ok(Wql::CreateEntity {
                 name: "hello_world".to_owned(),
                 uniques: Some(vec!["hello".to_string(), "world".to_string()]),
-                encrypts: None
+                encrypts: None,
+                schema: None,
+                if_not_exists: false
             }),
             parse_wql("create ENTITY hello_world UNIQUES #{hello, world}")
         );
@@ -43,7 +56,9 @@ mod tests {
             Ok(Wql::CreateEntity {
                 name: "hello_world".to_owned(),
                 encrypts: Some(vec!["hello".to_string(), "world".to_string()]),
-                uniques: None
+                uniques: None,
+                schema: None,
+                if_not_exists: false
             }),
             parse_wql("create ENTITY hello_world Encrypt #{hello, world}")
         );
@@ -56,6 +71,8 @@ mod tests {
                 name: "hello_world".to_owned(),
                 encrypts: Some(vec!["hello2".to_string(), "world2".to_string()]),
                 uniques: Some(vec!["hello".to_string(), "world".to_string()]),
+                schema: None,
+                if_not_exists: false
             }),
             parse_wql(
                 "create ENTITY hello_world UNIQUES #{hello, world} Encrypt #{hello2, world2}"
@@ -63,19 +80,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_content_schema() {
+        assert_eq!(
+            Ok(Wql::CreateEntity {
+                name: "users".to_owned(),
+                uniques: Some(vec!["name".to_string()]),
+                encrypts: None,
+                schema: Some(
+                    [
+                        ("name".to_string(), SchemaType::String),
+                        ("age".to_string(), SchemaType::Integer),
+                        ("tags".to_string(), SchemaType::Vector),
+                        ("born".to_string(), SchemaType::DateTime),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, SchemaType>>()
+                ),
+                if_not_exists: false
+            }),
+            parse_wql(
+                "CREATE ENTITY users SCHEMA {name: String, age: Integer, tags: Vector, born: DateTime} UNIQUES #{name}"
+            )
+        );
+    }
+
+    #[test]
+    fn check_schema() {
+        let create = parse_wql("CREATE ENTITY users SCHEMA {name: String, age: Integer}").unwrap();
+        let valid = parse_wql("INSERT {name: \"ana\", age: 30i, other: 1.5} INTO users").unwrap();
+        let invalid = parse_wql("INSERT [{age: 30i}, {age: \"thirty\"}] INTO users").unwrap();
+
+        assert_eq!(Ok(()), valid.check_schema(&create));
+        assert!(invalid.check_schema(&create).is_err());
+    }
+
+    #[test]
+    fn alter_entity() {
+        assert_eq!(
+            Ok(Wql::AlterEntity {
+                name: "users".to_owned(),
+                changes: vec![
+                    Alteration::Add(CreateOptions::UNIQUES, vec!["email".to_string()]),
+                    Alteration::Drop(CreateOptions::ENCRYPT, vec!["ssn".to_string()]),
+                ]
+            }),
+            parse_wql("ALTER ENTITY users ADD UNIQUES #{email} DROP ENCRYPT #{ssn}")
+        );
+    }
+
+    #[test]
+    fn alter_entity_without_changes() {
+        assert!(parse_wql("ALTER ENTITY users").is_err());
+    }
+
+    #[test]
+    fn show_entities() {
+        assert_eq!(Ok(Wql::ShowEntities), parse_wql("SHOW ENTITIES"));
+        assert!(parse_wql("SHOW users").is_err());
+        assert!(parse_wql("SHOW ENTITIES garbage").is_err());
+        assert!(parse_wql("SHOWENTITIES").is_err());
+    }
+
+    #[test]
+    fn describe_entity() {
+        assert_eq!(
+            Ok(Wql::Describe {
+                entity: "users".to_owned()
+            }),
+            parse_wql("DESCRIBE users")
+        );
+        assert_eq!(
+            Ok(Wql::Describe {
+                entity: "users".to_owned()
+            }),
+            parse_wql("DESCRIBE ENTITY users")
+        );
+        assert!(parse_wql("DESCRIBE users garbage").is_err());
+        assert!(parse_wql("DESCRIBEusers").is_err());
+    }
+
+    #[test]
+    fn drop_entity() {
+        assert_eq!(
+            Ok(Wql::DropEntity {
+                name: "users".to_owned()
+            }),
+            parse_wql("DROP ENTITY users")
+        );
+        assert!(parse_wql("DROP users").is_err());
+        assert!(parse_wql("DROP ENTITY users garbage").is_err());
+        assert!(parse_wql("DROP ENTITYusers").is_err());
+    }
+
     #[test]
     fn simple_insert() {
         assert_eq!(
             Ok(Wql::Insert {
                 entity: String::from("my_entity"),
                 id: None,
-                content: vec![
+                content: [
                     (String::from("hello"), Types::String("world".to_string())),
                     (String::from("age"), Types::Integer(30)),
                 ]
                 .iter()
                 .cloned()
-                .collect::<HashMap<String, Types>>()
+                .collect::<HashMap<String, Types>>(),
+                if_not_exists: false
             }),
             parse_wql("Insert {hello: \"world\", age: 30i} INTO my_entity")
         )
@@ -88,18 +200,104 @@ mod tests {
                 Wql::Insert {
                     entity: String::from("my_entity"),
                     id: Some(Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()),
-                    content: vec![
+                    content: [
                         (String::from("hello"), Types::String("world".to_string())),
                         (String::from("age"), Types::Integer(30)),
                     ].iter()
                     .cloned()
-                    .collect::<HashMap<String, Types>>()
+                    .collect::<HashMap<String, Types>>(),
+                    if_not_exists: false
                 }
             ),
             parse_wql("Insert {hello: \"world\", age: 30i} INTO my_entity WITH 2e796540-ee72-40fd-b4a2-a2315d697d00")
         )
     }
 
+    #[test]
+    fn insert_many() {
+        assert_eq!(
+            Ok(Wql::InsertMany {
+                entity: String::from("my_entity"),
+                contents: vec![
+                    [
+                        (String::from("hello"), Types::String("world".to_string())),
+                        (String::from("age"), Types::Integer(30)),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, Types>>(),
+                    [(String::from("hello"), Types::String("there".to_string()))]
+                        .iter()
+                        .cloned()
+                        .collect::<HashMap<String, Types>>(),
+                ]
+            }),
+            parse_wql("INSERT [{hello: \"world\", age: 30i}, {hello: \"there\"}] INTO my_entity")
+        )
+    }
+
+    #[test]
+    fn insert_many_empty() {
+        assert!(parse_wql("INSERT [] INTO my_entity").is_err());
+    }
+
+    #[test]
+    fn insert_many_on_conflict() {
+        assert!(parse_wql("INSERT [{a: 1i}] INTO users ON CONFLICT (a) DO NOTHING").is_err());
+    }
+
+    #[test]
+    fn upsert_update_set() {
+        assert_eq!(
+            Ok(Wql::Upsert {
+                entity: String::from("users"),
+                content: [
+                    (String::from("email"), Types::String("a@b.c".to_string())),
+                    (String::from("age"), Types::Integer(30)),
+                ]
+                .iter()
+                .cloned()
+                .collect::<HashMap<String, Types>>(),
+                id: None,
+                conflict: vec![String::from("email")],
+                action: ConflictAction::UpdateSet
+            }),
+            parse_wql(
+                "INSERT {email: \"a@b.c\", age: 30i} INTO users ON CONFLICT (email) UPDATE SET"
+            )
+        )
+    }
+
+    #[test]
+    fn upsert_do_nothing_with_id() {
+        assert_eq!(
+            Ok(Wql::Upsert {
+                entity: String::from("users"),
+                content: [(String::from("email"), Types::String("a@b.c".to_string()))]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, Types>>(),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                conflict: vec![String::from("email")],
+                action: ConflictAction::DoNothing
+            }),
+            parse_wql("INSERT {email: \"a@b.c\"} INTO users WITH 2e796540-ee72-40fd-b4a2-a2315d697d00 ON CONFLICT (email) DO NOTHING")
+        )
+    }
+
+    #[test]
+    fn upsert_conflict_uniques() {
+        let create = parse_wql("CREATE ENTITY users UNIQUES #{email}").unwrap();
+        let upsert =
+            parse_wql("INSERT {email: \"a@b.c\"} INTO users ON CONFLICT (email) DO NOTHING")
+                .unwrap();
+        let not_unique =
+            parse_wql("INSERT {age: 3i} INTO users ON CONFLICT (age) DO NOTHING").unwrap();
+
+        assert_eq!(Ok(()), upsert.check_conflict_uniques(&create));
+        assert!(not_unique.check_conflict_uniques(&create).is_err());
+    }
+
     #[test]
     fn update_set() {
         assert_eq!(
@@ -107,7 +305,7 @@ mod tests {
                 Wql::UpdateSet {
                     name: String::from("this_entity"),
                     id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
-                    content: vec![
+                    content: [
                         (String::from("hello"), Types::String("world".to_string())),
                         (String::from("age"), Types::Integer(30)),
                     ].iter()
@@ -119,6 +317,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn update_set_paths() {
+        assert_eq!(
+            Ok(
+                Wql::UpdateSet {
+                    name: String::from("this_entity"),
+                    id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                    content: [
+                        (String::from("address.city"), Types::String("Seoul".to_string())),
+                        (String::from("tags[0]"), Types::String("vip".to_string())),
+                    ].iter()
+                    .cloned()
+                    .collect::<HashMap<String, Types>>()
+                }
+            ),
+            parse_wql("UPDATE this_entity SET {address.city: \"Seoul\", tags[0]: \"vip\"} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        )
+    }
+
     #[test]
     fn update_content() {
         assert_eq!(
@@ -126,24 +343,51 @@ mod tests {
                 Wql::UpdateContent {
                     name: String::from("this_entity"),
                     id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
-                    content: vec![
-                        (String::from("hello"), Types::String("world".to_string())),
-                        (String::from("age"), Types::Integer(30)),
+                    content: [
+                        (String::from("hello"), ContentOp::Set(Types::String("world".to_string()))),
+                        (String::from("age"), ContentOp::Set(Types::Integer(30))),
                     ].iter()
                     .cloned()
-                    .collect::<HashMap<String, Types>>()
+                    .collect::<HashMap<String, ContentOp>>()
                 }
             ),
             parse_wql("UPDATE this_entity CONTENT {hello: \"world\", age: 30i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
         )
     }
 
+    #[test]
+    fn update_content_operators() {
+        assert_eq!(
+            Ok(
+                Wql::UpdateContent {
+                    name: String::from("this_entity"),
+                    id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                    content: [
+                        (String::from("count"), ContentOp::Add(Types::Integer(1))),
+                        (String::from("tags"), ContentOp::Append(Types::Vector(vec![Types::String("x".to_string())]))),
+                        (String::from("cash"), ContentOp::Add(Types::Float(-10.5))),
+                        (String::from("old"), ContentOp::Remove),
+                        (String::from("debt"), ContentOp::Set(Types::Integer(-5))),
+                    ].iter()
+                    .cloned()
+                    .collect::<HashMap<String, ContentOp>>()
+                }
+            ),
+            parse_wql("UPDATE this_entity CONTENT {count: +1i, tags: append [\"x\"], cash: -10.5, old: remove, debt: set -5i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        );
+        assert!(parse_wql(
+            "UPDATE this_entity SET {count: +1i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00"
+        )
+        .is_err());
+    }
+
     #[test]
     fn evict_id() {
         assert_eq!(
             Ok(Wql::Evict {
                 entity: String::from("evict_entity"),
-                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok()
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                keys: None
             }),
             parse_wql("EVICT 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM evict_entity")
         )
@@ -154,20 +398,861 @@ mod tests {
         assert_eq!(
             Ok(Wql::Evict {
                 entity: String::from("evict_entity"),
-                id: None
+                id: None,
+                keys: None
             }),
             parse_wql("EVICT evict_entity")
         )
     }
 
+    #[test]
+    fn evict_keys() {
+        assert_eq!(
+            Ok(Wql::Evict {
+                entity: String::from("users"),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                keys: Some(vec!["ssn".to_string(), "address".to_string()])
+            }),
+            parse_wql("EVICT #{ssn, address} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        );
+        assert!(parse_wql("EVICT #{ssn, address} FROM users").is_err());
+        assert!(parse_wql("EVICT #{} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00").is_err());
+        assert!(parse_wql(
+            "EVICT #{ssn} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00 garbage"
+        )
+        .is_err());
+    }
+
     #[test]
     fn delete() {
         assert_eq!(
             Ok(Wql::Delete {
                 entity: String::from("delete_entity"),
-                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                at: None
             }),
             parse_wql("Delete 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM delete_entity")
         )
     }
+
+    #[test]
+    fn delete_at() {
+        assert_eq!(
+            Ok(Wql::Delete {
+                entity: String::from("delete_entity"),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                at: Some("2014-11-28T21:00:09+09:00".parse().unwrap())
+            }),
+            parse_wql("Delete 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM delete_entity AT 2014-11-28T21:00:09+09:00")
+        )
+    }
+
+    #[test]
+    fn delete_rejects_unparsed_input() {
+        assert!(parse_wql(
+            "DELETE 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM delete_entity at 2014-11-28T12:00:09Z"
+        )
+        .is_err());
+        assert!(parse_wql(
+            "DELETE 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM delete_entity AT 2014-11-28T12:00:09Z extra"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn select_all() {
+        assert_eq!(
+            Ok(Wql::Select {
+                entity: String::from("my_entity"),
+                arg: ToSelect::All,
+                id: None,
+                functions: vec![]
+            }),
+            parse_wql("SELECT * FROM my_entity")
+        )
+    }
+
+    #[test]
+    fn select_keys() {
+        assert_eq!(
+            Ok(Wql::Select {
+                entity: String::from("my_entity"),
+                arg: ToSelect::Keys(vec!["name".to_string(), "age".to_string()]),
+                id: None,
+                functions: vec![]
+            }),
+            parse_wql("select #{name, age} FROM my_entity")
+        )
+    }
+
+    #[test]
+    fn select_rejects_unparsed_input() {
+        assert!(parse_wql("SELECT * FROM users where {age > 1i}").is_err());
+        assert!(parse_wql("SELECT * FROM users something else").is_err());
+        assert!(parse_wql("SELECT * FROM users ;").is_ok());
+    }
+
+    #[test]
+    fn select_id() {
+        assert_eq!(
+            Ok(Wql::Select {
+                entity: String::from("my_entity"),
+                arg: ToSelect::Keys(vec!["name".to_string()]),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                functions: vec![]
+            }),
+            parse_wql("SELECT #{name} FROM my_entity ID 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        )
+    }
+
+    #[test]
+    fn select_ids() {
+        assert_eq!(
+            Ok(Wql::SelectIds {
+                entity: String::from("my_entity"),
+                arg: ToSelect::All,
+                ids: vec![
+                    Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                    Uuid::parse_str("634f6c5b-476f-4cc0-97d0-c1c9468cf8d8").unwrap(),
+                ],
+                functions: vec![]
+            }),
+            parse_wql("SELECT * FROM my_entity IDS IN #{2e796540-ee72-40fd-b4a2-a2315d697d00, 634f6c5b-476f-4cc0-97d0-c1c9468cf8d8}")
+        )
+    }
+
+    #[test]
+    fn select_where() {
+        assert_eq!(
+            Ok(Wql::SelectWhere {
+                entity: String::from("users"),
+                arg: ToSelect::All,
+                clauses: vec![
+                    Clause::Compare(
+                        "age".to_string(),
+                        Comparison::GreaterOrEqual,
+                        Types::Integer(18)
+                    ),
+                    Clause::Or(vec![
+                        Clause::Like("name".to_string(), "%ana%".to_string()),
+                        Clause::Not(Box::new(Clause::In(
+                            "city".to_string(),
+                            vec![Types::String("Seoul".to_string())]
+                        ))),
+                    ]),
+                ],
+                functions: vec![]
+            }),
+            parse_wql(
+                "SELECT * FROM users WHERE { age >= 18i, or(name like \"%ana%\", not(city in [\"Seoul\"])) }"
+            )
+        )
+    }
+
+    #[test]
+    fn select_where_invalid() {
+        assert!(parse_wql("SELECT * FROM users WHERE { age => 18i }").is_err());
+    }
+
+    #[test]
+    fn select_when_at() {
+        assert_eq!(
+            Ok(Wql::SelectWhen {
+                entity: String::from("users"),
+                arg: ToSelect::Keys(vec!["name".to_string()]),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                time: "2014-11-28T21:00:09+09:00".parse().unwrap()
+            }),
+            parse_wql(
+                "SELECT #{name} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00 WHEN AT 2014-11-28T21:00:09+09:00"
+            )
+        )
+    }
+
+    #[test]
+    fn select_when_range() {
+        assert_eq!(
+            Ok(Wql::SelectWhenRange {
+                entity: String::from("users"),
+                arg: ToSelect::All,
+                id: None,
+                start: "2014-11-28T12:00:09Z".parse().unwrap(),
+                end: "2014-11-29T12:00:09Z".parse().unwrap()
+            }),
+            parse_wql(
+                "SELECT * FROM users WHEN START 2014-11-28T12:00:09Z END 2014-11-29T12:00:09Z"
+            )
+        )
+    }
+
+    #[test]
+    fn select_when_with_ids() {
+        assert!(parse_wql(
+            "SELECT * FROM users IDS IN #{2e796540-ee72-40fd-b4a2-a2315d697d00} WHEN AT 2014-11-28T12:00:09Z"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn select_where_functions() {
+        assert_eq!(
+            Ok(Wql::SelectWhere {
+                entity: String::from("users"),
+                arg: ToSelect::Keys(vec!["name".to_string(), "age".to_string()]),
+                clauses: vec![Clause::Compare(
+                    "age".to_string(),
+                    Comparison::Greater,
+                    Types::Integer(18)
+                )],
+                functions: vec![
+                    SelectFunction::OrderBy("age".to_string(), Order::Desc),
+                    SelectFunction::Offset(20),
+                    SelectFunction::Limit(10),
+                ]
+            }),
+            parse_wql(
+                "SELECT #{name, age} FROM users WHERE {age > 18i} ORDER BY age :desc OFFSET 20 LIMIT 10"
+            )
+        )
+    }
+
+    #[test]
+    fn select_clauses_out_of_place() {
+        let id = "2e796540-ee72-40fd-b4a2-a2315d697d00";
+        let at = "2014-11-28T12:00:09Z";
+        for query in [
+            "SELECT * FROM users LIMIT 10 WHERE {age > 1i}".to_string(),
+            format!("SELECT * FROM users LIMIT 10 ID {}", id),
+            format!("SELECT * FROM users LIMIT 10 IDS IN #{{{}}}", id),
+            format!("SELECT * FROM users LIMIT 10 WHEN AT {}", at),
+            format!("SELECT * FROM users WHEN AT {} ID {}", at, id),
+            "SELECT * FROM users ORDER BY age WHERE {age > 1i}".to_string(),
+            "SELECT * FROM users WHERE {age > 1i} LIMT 10".to_string(),
+            "SELECT * FROM users COUNTRY".to_string(),
+        ] {
+            assert!(parse_wql(&query).is_err(), "`{}` should not parse", query);
+        }
+    }
+
+    #[test]
+    fn order_by_types() {
+        let mut ages = vec![Types::Integer(3), Types::Integer(1), Types::Integer(2)];
+        ages.sort_by(|a, b| Order::Desc.compare(a, b));
+        assert_eq!(
+            vec![Types::Integer(3), Types::Integer(2), Types::Integer(1)],
+            ages
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(
+            Ok(Wql::Check {
+                entity: String::from("users"),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                content: [
+                    (String::from("password"), Types::String("s3cret".to_string())),
+                    (String::from("pin"), Types::Integer(1234)),
+                ]
+                .iter()
+                .cloned()
+                .collect::<HashMap<String, Types>>()
+            }),
+            parse_wql("CHECK {password: \"s3cret\", pin: 1234i} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        )
+    }
+
+    #[test]
+    fn check_without_id() {
+        assert!(parse_wql("CHECK {password: \"s3cret\"} FROM users").is_err());
+    }
+
+    #[test]
+    fn match_all_update() {
+        assert_eq!(
+            Ok(Wql::MatchUpdate {
+                entity: String::from("users"),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                content: [(String::from("name"), Types::String("y".to_string()))]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, Types>>(),
+                conditions: Clause::And(vec![
+                    Clause::Compare(
+                        "name".to_string(),
+                        Comparison::Equal,
+                        Types::String("x".to_string())
+                    ),
+                    Clause::Compare("age".to_string(), Comparison::Greater, Types::Integer(30)),
+                ])
+            }),
+            parse_wql("MATCH ALL(name == \"x\", age > 30i) UPDATE users SET {name: \"y\"} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        )
+    }
+
+    #[test]
+    fn match_any_update() {
+        assert_eq!(
+            Ok(Wql::MatchUpdate {
+                entity: String::from("users"),
+                id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                content: [(String::from("age"), Types::Integer(31))]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, Types>>(),
+                conditions: Clause::Or(vec![
+                    Clause::Compare("age".to_string(), Comparison::Equal, Types::Integer(30)),
+                    Clause::Compare("age".to_string(), Comparison::Equal, Types::Integer(31)),
+                ])
+            }),
+            parse_wql("MATCH ANY(age == 30i, age == 31i) UPDATE users SET {age: 31i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
+        )
+    }
+
+    #[test]
+    fn match_update_content_is_rejected() {
+        assert!(parse_wql("MATCH ALL(age == 30i) UPDATE users CONTENT {age: 1i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00").is_err());
+    }
+
+    #[test]
+    fn match_update_rejects_unparsed_input() {
+        assert!(parse_wql("MATCH ALL(age == 30i) UPDATE users SET {age: 1i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00 garbage").is_err());
+        assert!(parse_wql(
+            "UPDATE users SET {age: 1i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00 garbage"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn relation_intersect_key() {
+        assert_eq!(
+            Ok(Wql::Relation {
+                operation: RelationType::Intersect,
+                mode: RelationMode::Key,
+                queries: (
+                    Box::new(Wql::Select {
+                        entity: String::from("users"),
+                        arg: ToSelect::Keys(vec!["name".to_string(), "age".to_string()]),
+                        id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                        functions: vec![]
+                    }),
+                    Box::new(Wql::SelectWhen {
+                        entity: String::from("users"),
+                        arg: ToSelect::All,
+                        id: Uuid::parse_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                        time: "2014-11-28T12:00:09Z".parse().unwrap()
+                    })
+                )
+            }),
+            parse_wql("RELATION INTERSECT KEY (SELECT #{name, age} FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00, SELECT * FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00 WHEN AT 2014-11-28T12:00:09Z)")
+        )
+    }
+
+    #[test]
+    fn relation_union_hash() {
+        assert_eq!(
+            Ok(Wql::Relation {
+                operation: RelationType::Union,
+                mode: RelationMode::Hash,
+                queries: (
+                    Box::new(Wql::SelectWhere {
+                        entity: String::from("a"),
+                        arg: ToSelect::All,
+                        clauses: vec![Clause::In(
+                            "x".to_string(),
+                            vec![Types::Integer(1), Types::Integer(2)]
+                        )],
+                        functions: vec![]
+                    }),
+                    Box::new(Wql::Select {
+                        entity: String::from("b"),
+                        arg: ToSelect::All,
+                        id: None,
+                        functions: vec![]
+                    })
+                )
+            }),
+            parse_wql(
+                "RELATION union hash (SELECT * FROM a WHERE {x in [1i, 2i]}, SELECT * FROM b)"
+            )
+        )
+    }
+
+    #[test]
+    fn relation_requires_two_selects() {
+        assert!(parse_wql("RELATION DIFFERENCE KEY (SELECT * FROM a)").is_err());
+        assert!(parse_wql("RELATION DIFFERENCE KEY (SELECT * FROM a, EVICT a)").is_err());
+        assert!(
+            parse_wql("RELATION UNION KEY (SELECT * FROM a, SELECT * FROM b) garbage").is_err()
+        );
+    }
+
+    #[test]
+    fn join() {
+        assert_eq!(
+            Ok(Wql::Join {
+                entities: (String::from("users"), String::from("orders")),
+                keys: (String::from("id"), String::from("user_id")),
+                queries: (
+                    Box::new(Wql::Select {
+                        entity: String::from("users"),
+                        arg: ToSelect::Keys(vec!["id".to_string(), "name".to_string()]),
+                        id: None,
+                        functions: vec![]
+                    }),
+                    Box::new(Wql::SelectWhere {
+                        entity: String::from("orders"),
+                        arg: ToSelect::All,
+                        clauses: vec![Clause::Compare(
+                            "total".to_string(),
+                            Comparison::Greater,
+                            Types::Float(10.5)
+                        )],
+                        functions: vec![]
+                    })
+                )
+            }),
+            parse_wql("JOIN (users:id, orders:user_id) SELECT #{id, name} FROM users | SELECT * FROM orders WHERE {total > 10.5}")
+        )
+    }
+
+    #[test]
+    fn join_entities_mismatch() {
+        assert!(parse_wql(
+            "JOIN (users:id, orders:user_id) SELECT * FROM orders | SELECT * FROM users"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn transaction() {
+        assert_eq!(
+            Ok(Wql::Transaction(vec![
+                Wql::Insert {
+                    entity: String::from("accounts"),
+                    content: [(String::from("cash"), Types::Float(10.5))]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    id: None,
+                    if_not_exists: false
+                },
+                Wql::UpdateContent {
+                    name: String::from("wallets"),
+                    id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                    content: [(String::from("cash"), ContentOp::Add(Types::Float(-10.5)))]
+                        .iter()
+                        .cloned()
+                        .collect()
+                },
+            ])),
+            parse_wql("BEGIN; INSERT {cash: 10.5} INTO accounts; UPDATE wallets CONTENT {cash: -10.5} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00; COMMIT")
+        )
+    }
+
+    #[test]
+    fn transaction_errors() {
+        assert!(parse_wql("BEGIN; INSERT {a: 1i} INTO x;").is_err());
+        assert!(parse_wql("BEGIN; BEGIN; INSERT {a: 1i} INTO x; COMMIT; COMMIT").is_err());
+        assert!(parse_wql("BEGIN; INSERT {a: 1i} INTO x; COMMIT; INSERT {a: 2i} INTO x").is_err());
+        assert!(parse_wql("BEGIN INSERT {a: 1i} INTO x; COMMIT").is_err());
+        assert!(parse_wql("COMMIT").is_err());
+    }
+
+    #[test]
+    fn script() {
+        let script = "
+            CREATE ENTITY accounts UNIQUES #{email};
+            INSERT {email: \"a;b@c.com\"} INTO accounts;
+            BEGIN;
+            INSERT {cash: 1i} INTO wallets;
+            COMMIT;
+            SHOW ENTITIES;
+        ";
+        assert_eq!(
+            Ok(vec![
+                Wql::CreateEntity {
+                    name: String::from("accounts"),
+                    uniques: Some(vec![String::from("email")]),
+                    encrypts: None,
+                    schema: None,
+                    if_not_exists: false
+                },
+                Wql::Insert {
+                    entity: String::from("accounts"),
+                    content: [(
+                        String::from("email"),
+                        Types::String("a;b@c.com".to_string())
+                    )]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                    id: None,
+                    if_not_exists: false
+                },
+                Wql::Transaction(vec![Wql::Insert {
+                    entity: String::from("wallets"),
+                    content: [(String::from("cash"), Types::Integer(1))]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    id: None,
+                    if_not_exists: false
+                }]),
+                Wql::ShowEntities,
+            ]),
+            parse_wql_script(script)
+        )
+    }
+
+    #[test]
+    fn script_missing_semicolon() {
+        assert!(parse_wql_script(
+            "UPDATE users SET {a: 1i} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00\nDROP ENTITY users;"
+        )
+        .is_err());
+        assert!(parse_wql_script("SHOW ENTITIES\nDROP ENTITY users;").is_err());
+        assert!(parse_wql_script("DROP ENTITY orders\nINSERT {a: 1i} INTO users;").is_err());
+        assert!(parse_wql_script("EVICT users\nSHOW ENTITIES;").is_err());
+        assert!(parse_wql_script(
+            "EVICT 2e796540-ee72-40fd-b4a2-a2315d697d00 FROM users\nSHOW ENTITIES;"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn script_error_index() {
+        match parse_wql_script("SHOW ENTITIES; BEGIN; SHOW ENTITIES; COMMIT; INSERT INTO x;") {
+            Err(WqlError::Script(index, _)) => assert_eq!(index, 2),
+            other => panic!("expected a script error, got {:?}", other),
+        }
+        match parse_wql_script("SHOW ENTITIES; BEGIN; SHOW ENTITIES;") {
+            Err(WqlError::Script(index, _)) => assert_eq!(index, 1),
+            other => panic!("expected a script error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            Ok(vec![
+                Wql::Insert {
+                    entity: String::from("notes"),
+                    content: [(
+                        String::from("text"),
+                        Types::String("-- kept; /* too */".to_string())
+                    )]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                    id: None,
+                    if_not_exists: false
+                },
+                Wql::Transaction(vec![Wql::ShowEntities]),
+            ]),
+            parse_wql_script(
+                "-- seed notes
+                INSERT {
+                    text: \"-- kept; /* too */\" -- the note
+                } INTO notes; /* a; block */
+                BEGIN -- one statement
+                ; SHOW ENTITIES; COMMIT /* done */;
+                -- trailing comment"
+            )
+        );
+        assert_eq!(
+            Ok(Wql::ShowEntities),
+            parse_wql("/* list */ SHOW /* all */ ENTITIES")
+        );
+        assert!(parse_wql_script("SHOW ENTITIES; /* unterminated ; SHOW ENTITIES").is_err());
+        assert!(parse_wql("/* unterminated SHOW ENTITIES").is_err());
+    }
+
+    #[test]
+    fn prepared_query() {
+        let prepared =
+            prepare_wql("SELECT * FROM users WHERE {name == $1, age >= :min_age}").unwrap();
+        let params: HashMap<Placeholder, Types> = [
+            (
+                Placeholder::Index(1),
+                Types::String("\"; EVICT users".to_string()),
+            ),
+            (Placeholder::Name("min_age".to_string()), Types::Integer(18)),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        assert_eq!(
+            Ok(Wql::SelectWhere {
+                entity: String::from("users"),
+                arg: ToSelect::All,
+                clauses: vec![
+                    Clause::Compare(
+                        "name".to_string(),
+                        Comparison::Equal,
+                        Types::String("\"; EVICT users".to_string())
+                    ),
+                    Clause::Compare(
+                        "age".to_string(),
+                        Comparison::GreaterOrEqual,
+                        Types::Integer(18)
+                    ),
+                ],
+                functions: vec![]
+            }),
+            prepared.bind(&params)
+        );
+        assert!(prepared.bind(&HashMap::new()).is_err());
+
+        let nested: HashMap<Placeholder, Types> = [
+            (
+                Placeholder::Index(1),
+                Types::Vector(vec![Types::Placeholder(Placeholder::Index(1))]),
+            ),
+            (Placeholder::Name("min_age".to_string()), Types::Integer(18)),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert!(prepared.bind(&nested).is_err());
+    }
+
+    #[test]
+    fn prepared_add_requires_number() {
+        let prepared =
+            prepare_wql("UPDATE users CONTENT {a: +$1} INTO 2e796540-ee72-40fd-b4a2-a2315d697d00")
+                .unwrap();
+        let bind = |value: Types| {
+            prepared.bind(
+                &[(Placeholder::Index(1), value)]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<Placeholder, Types>>(),
+            )
+        };
+
+        assert_eq!(
+            Ok(Wql::UpdateContent {
+                name: String::from("users"),
+                id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap(),
+                content: [(String::from("a"), ContentOp::Add(Types::Integer(2)))]
+                    .iter()
+                    .cloned()
+                    .collect::<HashMap<String, ContentOp>>()
+            }),
+            bind(Types::Integer(2))
+        );
+        assert!(bind(Types::String("2".to_string())).is_err());
+    }
+
+    #[test]
+    fn placeholders_require_prepare() {
+        assert!(parse_wql("INSERT {name: $1} INTO users").is_err());
+        assert!(parse_wql_script("INSERT {name: :name} INTO users;").is_err());
+        assert!(prepare_wql("INSERT {name: $1, tags: [:tag]} INTO users").is_ok());
+    }
+
+    #[test]
+    fn script_variables() {
+        let queries = parse_wql_script(
+            "LET u = INSERT {name: \"Julia\"} INTO users;
+            BEGIN;
+            INSERT {owner: $u, items: [$u]} INTO orders;
+            COMMIT;",
+        )
+        .unwrap();
+
+        let id = match &queries[0] {
+            Wql::Insert { id: Some(id), .. } => *id,
+            other => panic!("expected an insert with an id, got {:?}", other),
+        };
+        assert_eq!(
+            Wql::Transaction(vec![Wql::Insert {
+                entity: String::from("orders"),
+                content: [
+                    (String::from("owner"), Types::Uuid(id)),
+                    (String::from("items"), Types::Vector(vec![Types::Uuid(id)])),
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+                id: None,
+                if_not_exists: false
+            }]),
+            queries[1]
+        );
+    }
+
+    #[test]
+    fn script_variables_keep_explicit_id() {
+        assert_eq!(
+            Ok(vec![
+                Wql::Insert {
+                    entity: String::from("users"),
+                    content: [(String::from("a"), Types::Integer(1))]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                    if_not_exists: false
+                },
+                Wql::Insert {
+                    entity: String::from("orders"),
+                    content: [(
+                        String::from("owner"),
+                        Types::Uuid(
+                            Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()
+                        )
+                    )]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                    id: None,
+                    if_not_exists: false
+                },
+            ]),
+            parse_wql_script(
+                "LET u = INSERT {a: 1i} INTO users WITH 2e796540-ee72-40fd-b4a2-a2315d697d00;
+                INSERT {owner: $u} INTO orders;"
+            )
+        );
+    }
+
+    #[test]
+    fn script_variable_errors() {
+        match parse_wql_script("INSERT {owner: $u} INTO orders; LET u = INSERT {a: 1i} INTO users;")
+        {
+            Err(WqlError::Script(index, _)) => assert_eq!(index, 0),
+            other => panic!("expected an undefined variable error, got {:?}", other),
+        }
+        match parse_wql_script(
+            "LET u = INSERT {a: 1i} INTO users; LET u = INSERT {a: 2i} INTO users;",
+        ) {
+            Err(WqlError::Script(index, _)) => assert_eq!(index, 1),
+            other => panic!("expected a duplicate variable error, got {:?}", other),
+        }
+        assert!(parse_wql_script("LET e = SHOW ENTITIES;").is_err());
+        assert!(parse_wql_script("LETTER = INSERT {a: 1i} INTO users;").is_err());
+        assert!(parse_wql("INSERT {owner: $u} INTO orders").is_err());
+    }
+
+    #[test]
+    fn if_not_exists() {
+        assert_eq!(
+            Ok(Wql::CreateEntity {
+                name: "users".to_owned(),
+                uniques: Some(vec!["email".to_string()]),
+                encrypts: None,
+                schema: None,
+                if_not_exists: true
+            }),
+            parse_wql("CREATE ENTITY IF NOT EXISTS users UNIQUES #{email}")
+        );
+        assert_eq!(
+            parse_wql("CREATE ENTITY IF NOT EXISTS users"),
+            parse_wql("CREATE ENTITY users IF NOT EXISTS")
+        );
+        assert_eq!(
+            Ok(Wql::Insert {
+                entity: "users".to_owned(),
+                content: [(String::from("email"), Types::String("a@b.c".to_string()))]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                id: Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").ok(),
+                if_not_exists: true
+            }),
+            parse_wql("INSERT {email: \"a@b.c\"} INTO users WITH 2e796540-ee72-40fd-b4a2-a2315d697d00 IF NOT EXISTS")
+        );
+        assert!(parse_wql(
+            "INSERT {email: \"a@b.c\"} INTO users IF NOT EXISTS ON CONFLICT (email) DO NOTHING"
+        )
+        .is_err());
+        assert!(parse_wql(
+            "INSERT {email: \"a@b.c\"} INTO users ON CONFLICT (email) DO NOTHING IF NOT EXISTS"
+        )
+        .is_err());
+        assert!(parse_wql("INSERT [{a: 1i}] INTO users IF NOT EXISTS").is_err());
+        assert!(
+            parse_wql_script("LET u = INSERT {email: \"a@b.c\"} INTO users IF NOT EXISTS;")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let create = parse_wql("CREATE ENTITY users UNIQUES #{email} ENCRYPT #{ssn}").unwrap();
+        let explain = parse_wql(
+            "EXPLAIN BEGIN; INSERT {email: \"a@b.c\", ssn: \"123\"} INTO users WITH 2e796540-ee72-40fd-b4a2-a2315d697d00; SELECT #{total} FROM orders WHERE {user == 2e796540-ee72-40fd-b4a2-a2315d697d00}; COMMIT",
+        )
+        .unwrap();
+
+        let query = match &explain {
+            Wql::Explain(query) => query,
+            other => panic!("expected an explain, got {:?}", other),
+        };
+        assert!(matches!(**query, Wql::Transaction(_)));
+
+        let explanation = explain.explain(&[create]);
+        assert!(!explanation.read_only);
+        assert_eq!(
+            "entities: orders, users\n\
+             ids: 2e796540-ee72-40fd-b4a2-a2315d697d00\n\
+             keys: email, ssn, total, user\n\
+             read only: false\n\
+             encrypted: ssn",
+            explanation.to_string()
+        );
+    }
+
+    #[test]
+    fn explain_dropped_encryption() {
+        let create = parse_wql("CREATE ENTITY users ENCRYPT #{ssn, pin}").unwrap();
+        let explanation = parse_wql("EXPLAIN ALTER ENTITY users DROP ENCRYPT #{ssn}")
+            .unwrap()
+            .explain(&[create]);
+        assert_eq!(
+            ["pin".to_string()]
+                .iter()
+                .cloned()
+                .collect::<BTreeSet<String>>(),
+            explanation.encrypted
+        );
+    }
+
+    #[test]
+    fn explain_script() {
+        assert_eq!(
+            Ok(vec![
+                Wql::Explain(Box::new(Wql::Transaction(vec![Wql::ShowEntities]))),
+                Wql::Explain(Box::new(Wql::ShowEntities)),
+            ]),
+            parse_wql_script(
+                "EXPLAIN BEGIN; SHOW ENTITIES; COMMIT;
+                EXPLAIN SHOW ENTITIES;"
+            )
+        );
+    }
+
+    #[test]
+    fn explain_read_only() {
+        let explanation =
+            parse_wql("EXPLAIN SELECT * FROM users ID 2e796540-ee72-40fd-b4a2-a2315d697d00")
+                .unwrap()
+                .explain(&[]);
+        assert_eq!(
+            Explanation {
+                entities: ["users".to_string()].iter().cloned().collect(),
+                ids: [Uuid::from_str("2e796540-ee72-40fd-b4a2-a2315d697d00").unwrap()]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                keys: Default::default(),
+                read_only: true,
+                encrypted: Default::default(),
+            },
+            explanation
+        );
+    }
 }